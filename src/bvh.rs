@@ -0,0 +1,252 @@
+use crate::geometry::Aabb;
+use crate::ray::Ray;
+
+// number of buckets the centroids are sorted into when searching for the best split
+const SAH_BUCKETS: usize = 12;
+// relative cost of visiting a node, compared to intersecting a single item
+const TRAVERSAL_COST: f32 = 0.125;
+// leaves larger than this are always split, even if the heuristic would keep them
+const MAX_LEAF_SIZE: usize = 4;
+// the traversal stack is a fixed size array, so the tree depth has to be limited
+const MAX_DEPTH: usize = 64;
+
+#[derive(Copy, Clone, Debug)]
+struct BvhNode {
+    bounds: Aabb,
+    // leaf: index of the first item in Bvh::items
+    // interior node: index of the second child. The first child is always stored right after its parent
+    offset: usize,
+    // number of items in a leaf, 0 for interior nodes
+    count: usize,
+    // split axis of interior nodes, used to visit the closer child first
+    axis: usize,
+}
+
+#[derive(Copy, Clone, Debug)]
+struct BuildItem {
+    index: usize,
+    bounds: Aabb,
+    centroid: f32,
+}
+
+// bounding volume hierarchy over anything with bounds. The items themselves are stored by the owner, the tree only
+// knows their indices
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Bvh {
+    // flattened tree in depth first order
+    nodes: Vec<BvhNode>,
+    // item indices in the order of the leaves, so every leaf references a contiguous range
+    items: Vec<usize>,
+}
+
+impl Bvh {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn build(bounds: &[Aabb]) -> Self {
+        let mut bvh = Self::new();
+        if bounds.is_empty() {
+            return bvh;
+        }
+        let mut items: Vec<BuildItem> = bounds.iter().enumerate().map(|(index, &bounds)| {
+            BuildItem {
+                index,
+                bounds,
+                centroid: 0.,
+            }
+        }).collect();
+        bvh.nodes.reserve(2 * items.len() - 1);
+        Self::build_node(&mut bvh.nodes, &mut items, 0, 0);
+        bvh.items = items.iter().map(|item| item.index).collect();
+        bvh
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub(crate) fn bounds(&self) -> Aabb {
+        self.nodes.first().map(|root| root.bounds).unwrap_or(Aabb::EMPTY)
+    }
+
+    fn build_node(nodes: &mut Vec<BvhNode>, items: &mut [BuildItem], first: usize, depth: usize) -> usize {
+        let bounds = items.iter().fold(Aabb::EMPTY, |b, item| b.union(&item.bounds));
+        let node_index = nodes.len();
+        nodes.push(BvhNode {
+            bounds,
+            offset: first,
+            count: items.len(),
+            axis: 0,
+        });
+        if items.len() == 1 || depth >= MAX_DEPTH {
+            return node_index;
+        }
+
+        // split along the axis in which the centroids are spread the most
+        let centroid_bounds = items.iter().fold(Aabb::EMPTY, |mut b, item| {
+            b.grow(item.bounds.centroid());
+            b
+        });
+        let axis = centroid_bounds.longest_axis();
+        let c_min = centroid_bounds.min[axis];
+        let extent = centroid_bounds.max[axis] - c_min;
+        for item in items.iter_mut() {
+            item.centroid = item.bounds.centroid()[axis];
+        }
+
+        // None: no useful split was found, fall back to equal counts on both sides
+        let sah_split = if extent <= 0. {
+            // all centroids are in the same spot, no heuristic can separate them
+            if items.len() <= MAX_LEAF_SIZE {
+                return node_index;
+            }
+            None
+        } else {
+            let bucket_of = |item: &BuildItem| {
+                (((item.centroid - c_min) / extent * SAH_BUCKETS as f32) as usize).min(SAH_BUCKETS - 1)
+            };
+            let mut counts = [0usize; SAH_BUCKETS];
+            let mut bucket_bounds = [Aabb::EMPTY; SAH_BUCKETS];
+            for item in items.iter() {
+                let b = bucket_of(item);
+                counts[b] += 1;
+                bucket_bounds[b] = bucket_bounds[b].union(&item.bounds);
+            }
+            // sweep from the right to know the cost of every right hand side in advance
+            let mut right_area = [0f32; SAH_BUCKETS];
+            let mut right_count = [0usize; SAH_BUCKETS];
+            let mut acc_bounds = Aabb::EMPTY;
+            let mut acc_count = 0;
+            for b in (1..SAH_BUCKETS).rev() {
+                acc_bounds = acc_bounds.union(&bucket_bounds[b]);
+                acc_count += counts[b];
+                right_area[b] = acc_bounds.surface_area();
+                right_count[b] = acc_count;
+            }
+            // split after the bucket with the lowest cost
+            let total_area = bounds.surface_area().max(f32::MIN_POSITIVE);
+            let mut best_cost = f32::INFINITY;
+            let mut best_split = 0;
+            let mut acc_bounds = Aabb::EMPTY;
+            let mut acc_count = 0;
+            for b in 0..SAH_BUCKETS - 1 {
+                acc_bounds = acc_bounds.union(&bucket_bounds[b]);
+                acc_count += counts[b];
+                let cost = TRAVERSAL_COST
+                    + (acc_count as f32 * acc_bounds.surface_area() + right_count[b + 1] as f32 * right_area[b + 1]) / total_area;
+                if cost < best_cost {
+                    best_cost = cost;
+                    best_split = b;
+                }
+            }
+            if items.len() <= MAX_LEAF_SIZE && best_cost >= items.len() as f32 {
+                return node_index;
+            }
+            let mid = partition(items, |item| bucket_of(item) <= best_split);
+            Some(mid).filter(|&mid| mid != 0 && mid != items.len())
+        };
+        let mid = match sah_split {
+            Some(mid) => mid,
+            None => {
+                let mid = items.len() / 2;
+                items.select_nth_unstable_by(mid, |a, b| a.centroid.total_cmp(&b.centroid));
+                mid
+            }
+        };
+
+        let (left, right) = items.split_at_mut(mid);
+        Self::build_node(nodes, left, first, depth + 1);
+        let second = Self::build_node(nodes, right, first + mid, depth + 1);
+        nodes[node_index].offset = second;
+        nodes[node_index].count = 0;
+        nodes[node_index].axis = axis;
+        node_index
+    }
+
+    // finds the closest item hit by the ray. intersect returns the distance and result for a single item and
+    // is only called with rays shortened to the closest hit found so far
+    pub(crate) fn intersect<T>(&self, ray: &Ray, mut intersect: impl FnMut(usize, &Ray) -> Option<(f32, T)>) -> Option<T> {
+        if self.nodes.is_empty() {
+            return None;
+        }
+        let mut ray = *ray;
+        let mut hit: Option<T> = None;
+        let mut stack = [0usize; MAX_DEPTH + 1];
+        let mut stack_size = 0;
+        let mut current = 0;
+        loop {
+            let node = &self.nodes[current];
+            if node.bounds.inner_does_intersect(&ray) {
+                if node.count > 0 {
+                    for &item in &self.items[node.offset..node.offset + node.count] {
+                        if let Some((distance, new_hit)) = intersect(item, &ray) {
+                            ray.max_distance = distance;
+                            hit = Some(new_hit);
+                        }
+                    }
+                } else {
+                    // visit the child closer to the ray origin first
+                    if ray.direction[node.axis] < 0. {
+                        stack[stack_size] = current + 1;
+                        current = node.offset;
+                    } else {
+                        stack[stack_size] = node.offset;
+                        current += 1;
+                    }
+                    stack_size += 1;
+                    continue;
+                }
+            }
+            if stack_size == 0 {
+                break;
+            }
+            stack_size -= 1;
+            current = stack[stack_size];
+        }
+        hit
+    }
+
+    // true as soon as any item is hit
+    pub(crate) fn does_intersect(&self, ray: &Ray, mut does_intersect: impl FnMut(usize) -> bool) -> bool {
+        if self.nodes.is_empty() {
+            return false;
+        }
+        // any hit will do, so there is no need to sort the children
+        let mut stack = [0usize; MAX_DEPTH + 1];
+        let mut stack_size = 0;
+        let mut current = 0;
+        loop {
+            let node = &self.nodes[current];
+            if node.bounds.inner_does_intersect(ray) {
+                if node.count > 0 {
+                    if self.items[node.offset..node.offset + node.count].iter().any(|&item| does_intersect(item)) {
+                        return true;
+                    }
+                } else {
+                    stack[stack_size] = node.offset;
+                    stack_size += 1;
+                    current += 1;
+                    continue;
+                }
+            }
+            if stack_size == 0 {
+                return false;
+            }
+            stack_size -= 1;
+            current = stack[stack_size];
+        }
+    }
+}
+
+// moves all items matching the predicate to the front and returns their number
+fn partition<T>(items: &mut [T], predicate: impl Fn(&T) -> bool) -> usize {
+    let mut mid = 0;
+    for i in 0..items.len() {
+        if predicate(&items[i]) {
+            items.swap(i, mid);
+            mid += 1;
+        }
+    }
+    mid
+}
@@ -0,0 +1,144 @@
+use crate::camera::PerspectiveCamera;
+use crate::math::{Vec2, Vec3};
+
+// how the opening angle of the camera is given
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum FieldOfView {
+    // in degrees, across the width of the image
+    Horizontal(f32),
+    // in degrees, across the height of the image
+    Vertical(f32),
+    // like a real camera: the image is the largest part of the sensor with its aspect ratio. Everything in mm
+    Lens {
+        sensor: Vec2,
+        focal_length: f32,
+    },
+}
+
+// sets up a perspective camera step by step, so the aspect ratio can be left to the resolution of the image
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct CameraBuilder {
+    origin: Vec3,
+    forward: Vec3,
+    up: Vec3,
+    fov: FieldOfView,
+    // derived from the resolution if not given
+    aspect: Option<f32>,
+}
+
+impl CameraBuilder {
+    // at the origin looking along +z with a horizontal field of view of 60°
+    pub fn new() -> Self {
+        CameraBuilder {
+            origin: Vec3::ZERO,
+            forward: Vec3::Z,
+            up: Vec3::Y,
+            fov: FieldOfView::Horizontal(60.),
+            aspect: None,
+        }
+    }
+
+    // up only has to point roughly upwards, it is made perpendicular to the view direction
+    pub fn look_at(mut self, eye: Vec3, target: Vec3, up: Vec3) -> Self {
+        self.origin = eye;
+        self.forward = target - eye;
+        self.up = up;
+        self
+    }
+
+    pub fn origin(mut self, origin: Vec3) -> Self {
+        self.origin = origin;
+        self
+    }
+
+    pub fn forward(mut self, forward: Vec3) -> Self {
+        self.forward = forward;
+        self
+    }
+
+    pub fn up(mut self, up: Vec3) -> Self {
+        self.up = up;
+        self
+    }
+
+    pub fn fov(mut self, fov: FieldOfView) -> Self {
+        self.fov = fov;
+        self
+    }
+
+    // overrides the aspect ratio of the resolution, for non square pixels
+    pub fn aspect(mut self, aspect: f32) -> Self {
+        self.aspect = Some(aspect);
+        self
+    }
+
+    // horizontal field of view in degrees for an image with the given aspect ratio
+    pub fn horizontal_fov(&self, aspect: f32) -> f32 {
+        let to_horizontal = |half_height: f32| 2. * (half_height * aspect).atan().to_degrees();
+        match self.fov {
+            FieldOfView::Horizontal(fov) => fov,
+            FieldOfView::Vertical(fov) => to_horizontal((fov.to_radians() / 2.).tan()),
+            FieldOfView::Lens { sensor, focal_length } => {
+                if aspect >= sensor.x / sensor.y {
+                    // wider than the sensor, the top and bottom are cut off
+                    2. * (sensor.x / 2. / focal_length).atan().to_degrees()
+                } else {
+                    to_horizontal(sensor.y / 2. / focal_length)
+                }
+            }
+        }
+    }
+
+    pub fn build(&self, resolution: (u32, u32)) -> PerspectiveCamera {
+        let aspect = self.aspect.unwrap_or(resolution.0 as f32 / resolution.1 as f32);
+        PerspectiveCamera::new(self.origin, self.forward, self.up, aspect, self.horizontal_fov(aspect))
+    }
+}
+
+impl Default for CameraBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod builder_tests {
+    use crate::camera::Camera;
+    use crate::camera::builder::{CameraBuilder, FieldOfView};
+    use crate::math::{ApproxEq, Vec2, Vec3, Vector};
+
+    #[test]
+    fn field_of_view() {
+        let builder = CameraBuilder::new().fov(FieldOfView::Vertical(90.));
+        assert!(builder.horizontal_fov(1.).a_eq(&90.));
+        // the top border is at 45° no matter how wide the image is
+        let cam = builder.build((300, 100));
+        let top = cam.at(Vec2::new(0.5, 0.), Vec2::ZERO).unwrap().direction;
+        assert!(top.a_eq(&Vec3::new(0., 1., 1.).normalized()));
+        let side = cam.at(Vec2::new(0., 0.5), Vec2::ZERO).unwrap().direction;
+        assert!(side.a_eq(&Vec3::new(3., 0., 1.).normalized()));
+        // a 50mm lens on a full frame sensor
+        let lens = CameraBuilder::new().fov(FieldOfView::Lens { sensor: Vec2::new(36., 24.), focal_length: 50. });
+        let horizontal = 2. * (18f32 / 50.).atan().to_degrees();
+        assert!(lens.horizontal_fov(1.5).a_eq(&horizontal));
+        // a square image is cropped from the middle of the sensor, so the vertical angle stays
+        let vertical = 2. * (12f32 / 50.).atan().to_degrees();
+        assert!(lens.horizontal_fov(1.).a_eq(&vertical));
+        assert!(lens.horizontal_fov(3.).a_eq(&horizontal));
+    }
+
+    #[test]
+    fn look_at() {
+        // up doesn't have to be perpendicular to the view direction
+        let cam = CameraBuilder::new()
+            .look_at(Vec3::new(0., 5., -5.), Vec3::ZERO, Vec3::Y)
+            .fov(FieldOfView::Horizontal(90.))
+            .build((100, 100));
+        let forward = Vec3::new(0., -1., 1.).normalized();
+        assert!(cam.at(Vec2::from(0.5), Vec2::ZERO).unwrap().direction.a_eq(&forward));
+        // the top of the image is 45° above the view direction, in the plane of forward and up
+        let top = cam.at(Vec2::new(0.5, 0.), Vec2::ZERO).unwrap().direction;
+        assert!(top.a_eq(&Vec3::Z));
+        assert_eq!(cam.at(Vec2::ZERO, Vec2::ZERO).unwrap().origin, Vec3::new(0., 5., -5.));
+    }
+}
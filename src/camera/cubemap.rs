@@ -0,0 +1,105 @@
+use crate::camera::{Camera, prepare_input};
+use crate::math::{Vec2, Vec3};
+use crate::ray::Ray;
+
+// arrangement of the six faces in the image, each face has to be square
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum CubemapLayout {
+    // one row of +x, -x, +y, -y, +z, -z in a 6:1 image
+    Strip,
+    // a 4:3 horizontal cross with -x, +z, +x, -z in the middle row and +y and -y above and below +z
+    Cross,
+}
+
+// renders the six faces of a cube around origin, aligned with the world axes. The faces are oriented like OpenGL
+// cube maps, so the image can be cut up and uploaded directly
+#[derive(Debug)]
+pub struct CubemapCamera {
+    origin: Vec3,
+    layout: CubemapLayout,
+}
+
+impl CubemapCamera {
+    pub fn new(origin: Vec3, layout: CubemapLayout) -> Self {
+        CubemapCamera {
+            origin,
+            layout,
+        }
+    }
+
+    // face index in OpenGL order and the position on it, or None for the empty parts of the cross
+    fn face(&self, coords: Vec2) -> Option<(usize, Vec2)> {
+        let (columns, rows) = match self.layout {
+            CubemapLayout::Strip => (6, 1),
+            CubemapLayout::Cross => (4, 3),
+        };
+        let cell = Vec2::new(coords.x * columns as f32, coords.y * rows as f32);
+        let (column, row) = ((cell.x as usize).min(columns - 1), (cell.y as usize).min(rows - 1));
+        let local = Vec2::new(cell.x - column as f32, cell.y - row as f32);
+        let face = match self.layout {
+            CubemapLayout::Strip => column,
+            CubemapLayout::Cross => match (column, row) {
+                (1, 0) => 2,
+                (0, 1) => 1,
+                (1, 1) => 4,
+                (2, 1) => 0,
+                (3, 1) => 5,
+                (1, 2) => 3,
+                _ => return None,
+            },
+        };
+        Some((face, local))
+    }
+}
+
+impl Camera for CubemapCamera {
+    fn at(&self, coords: Vec2, _lens: Vec2) -> Option<Ray> {
+        let (face, local) = self.face(coords)?;
+        // s to the right and t down on the face
+        let c = prepare_input(local);
+        let (s, t) = (c.x, c.y);
+        let direction = match face {
+            0 => Vec3::new(1., -t, -s),
+            1 => Vec3::new(-1., -t, s),
+            2 => Vec3::new(s, 1., t),
+            3 => Vec3::new(s, -1., -t),
+            4 => Vec3::new(s, -t, 1.),
+            _ => Vec3::new(-s, -t, -1.),
+        };
+        Some(Ray::new(self.origin, direction, None, None))
+    }
+}
+
+#[cfg(test)]
+mod cubemap_tests {
+    use crate::camera::Camera;
+    use crate::camera::cubemap::{CubemapCamera, CubemapLayout};
+    use crate::math::{ApproxEq, Vec2, Vec3};
+
+    #[test]
+    fn faces() {
+        let strip = CubemapCamera::new(Vec3::ZERO, CubemapLayout::Strip);
+        let axes = [Vec3::X, -Vec3::X, Vec3::Y, -Vec3::Y, Vec3::Z, -Vec3::Z];
+        for (i, axis) in axes.iter().enumerate() {
+            let ray = strip.at(Vec2::new((i as f32 + 0.5) / 6., 0.5), Vec2::ZERO).unwrap();
+            assert!(ray.direction.a_eq(axis), "{} {:?}", i, ray.direction);
+        }
+        let cross = CubemapCamera::new(Vec3::ZERO, CubemapLayout::Cross);
+        let at = |x: f32, y: f32| cross.at(Vec2::new(x / 4., y / 3.), Vec2::ZERO);
+        assert!(at(1.5, 1.5).unwrap().direction.a_eq(&Vec3::Z));
+        assert!(at(1.5, 0.5).unwrap().direction.a_eq(&Vec3::Y));
+        assert!(at(0.5, 0.5).is_none());
+        assert!(at(3.5, 2.5).is_none());
+        // neighbouring faces in the cross meet along their shared edge
+        let eps = 1e-4;
+        for t in [1.2, 1.5, 1.9] {
+            assert!(at(2. - eps, t).unwrap().direction.a_eq(&at(2. + eps, t).unwrap().direction));
+            assert!(at(1. - eps, t).unwrap().direction.a_eq(&at(1. + eps, t).unwrap().direction));
+            assert!(at(3. - eps, t).unwrap().direction.a_eq(&at(3. + eps, t).unwrap().direction));
+        }
+        for s in [1.2, 1.5, 1.9] {
+            assert!(at(s, 1. - eps).unwrap().direction.a_eq(&at(s, 1. + eps).unwrap().direction));
+            assert!(at(s, 2. - eps).unwrap().direction.a_eq(&at(s, 2. + eps).unwrap().direction));
+        }
+    }
+}
@@ -0,0 +1,59 @@
+use std::f32::consts::PI;
+use crate::camera::{Camera, orthonormal_frame};
+use crate::math::{Vec2, Vec3};
+use crate::ray::Ray;
+
+// the full sphere around origin: longitude along x with forward in the middle and right to the right, latitude along
+// y from up to down. Meant for a 2:1 image
+#[derive(Debug)]
+pub struct EquirectangularCamera {
+    // shared with the omni-directional stereo camera
+    pub(super) origin: Vec3,
+    pub(super) forward: Vec3,
+    pub(super) up: Vec3,
+    pub(super) right: Vec3,
+}
+
+impl EquirectangularCamera {
+    pub fn new(origin: Vec3, forward: Vec3, up: Vec3) -> Self {
+        let (forward, up, right) = orthonormal_frame(forward, up);
+        EquirectangularCamera {
+            origin,
+            forward,
+            up,
+            right,
+        }
+    }
+
+    pub(crate) fn direction(&self, coords: Vec2) -> Vec3 {
+        let (phi, theta) = ((coords.x - 0.5) * 2. * PI, coords.y * PI);
+        self.up * theta.cos() + (self.forward * phi.cos() + self.right * phi.sin()) * theta.sin()
+    }
+}
+
+impl Camera for EquirectangularCamera {
+    fn at(&self, coords: Vec2, _lens: Vec2) -> Option<Ray> {
+        Some(Ray::new(self.origin, self.direction(coords), None, None))
+    }
+}
+
+#[cfg(test)]
+mod equirectangular_tests {
+    use crate::camera::Camera;
+    use crate::camera::equirectangular::EquirectangularCamera;
+    use crate::math::{ApproxEq, Vec2, Vec3};
+
+    #[test]
+    fn directions() {
+        let cam = EquirectangularCamera::new(Vec3::new(1., 2., 3.), Vec3::new(0., 0., 2.), Vec3::new(0., 1., 0.3));
+        let at = |x, y| cam.at(Vec2::new(x, y), Vec2::ZERO).unwrap();
+        assert!(at(0.5, 0.5).direction.a_eq(&Vec3::Z));
+        assert_eq!(at(0.5, 0.5).origin, Vec3::new(1., 2., 3.));
+        // right is forward x up, like for the perspective camera
+        assert!(at(0.75, 0.5).direction.a_eq(&-Vec3::X));
+        assert!(at(0., 0.5).direction.a_eq(&-Vec3::Z));
+        assert!(at(1., 0.5).direction.a_eq(&-Vec3::Z));
+        assert!(at(0.3, 0.).direction.a_eq(&Vec3::Y));
+        assert!(at(0.8, 1.).direction.a_eq(&-Vec3::Y));
+    }
+}
@@ -0,0 +1,89 @@
+use crate::camera::{Camera, orthonormal_frame, prepare_input};
+use crate::math::{Vec2, Vec3, Vector};
+use crate::ray::Ray;
+
+// how the angle to forward maps onto the distance from the image center
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum FisheyeProjection {
+    // the distance grows linearly with the angle
+    Equidistant,
+    // equal solid angles cover equal areas of the image
+    Equisolid,
+}
+
+// a circular fisheye. The image circle touches the left and right border, everything outside of it stays black
+#[derive(Debug)]
+pub struct FisheyeCamera {
+    origin: Vec3,
+    forward: Vec3,
+    up: Vec3,
+    right: Vec3,
+    aspect: f32,
+    // angle between forward and the edge of the image circle, in radians
+    half_fov: f32,
+    projection: FisheyeProjection,
+}
+
+impl FisheyeCamera {
+    // fov in degrees across the image circle, at most 360
+    pub fn new(origin: Vec3, forward: Vec3, up: Vec3, aspect: f32, fov: f32, projection: FisheyeProjection) -> Self {
+        debug_assert!(fov > 0. && fov <= 360.);
+        let (forward, up, right) = orthonormal_frame(forward, up);
+        FisheyeCamera {
+            origin,
+            forward,
+            up,
+            right,
+            aspect,
+            half_fov: fov.to_radians() / 2.,
+            projection,
+        }
+    }
+}
+
+impl Camera for FisheyeCamera {
+    fn at(&self, coords: Vec2, _lens: Vec2) -> Option<Ray> {
+        let c = prepare_input(coords);
+        let p = Vec2::new(c.x, c.y / self.aspect);
+        let r = p.length();
+        if r > 1. {
+            return None;
+        }
+        let theta = match self.projection {
+            FisheyeProjection::Equidistant => r * self.half_fov,
+            FisheyeProjection::Equisolid => 2. * (r * (self.half_fov / 2.).sin()).asin(),
+        };
+        let sideways = if r > 0. { (self.right * p.x - self.up * p.y) / r } else { Vec3::ZERO };
+        Some(Ray::new(self.origin, self.forward * theta.cos() + sideways * theta.sin(), None, None))
+    }
+}
+
+#[cfg(test)]
+mod fisheye_tests {
+    use crate::camera::Camera;
+    use crate::camera::fisheye::{FisheyeCamera, FisheyeProjection};
+    use crate::math::{ApproxEq, Vec2, Vec3, Vector};
+
+    #[test]
+    fn projections() {
+        for projection in [FisheyeProjection::Equidistant, FisheyeProjection::Equisolid] {
+            let cam = FisheyeCamera::new(Vec3::ZERO, Vec3::Z, Vec3::Y, 2., 180., projection);
+            let at = |x, y| cam.at(Vec2::new(x, y), Vec2::ZERO);
+            assert!(at(0.5, 0.5).unwrap().direction.a_eq(&Vec3::Z));
+            // the edge of the circle is 90° to the side
+            assert!(at(1., 0.5).unwrap().direction.a_eq(&-Vec3::X));
+            assert!(at(0., 0.5).unwrap().direction.a_eq(&Vec3::X));
+            // the top of the image is halfway to the edge
+            let top = at(0.5, 0.).unwrap().direction;
+            assert!(top.y > 0. && top.x.a_eq(&0.));
+            assert!(at(0.05, 0.05).is_none());
+        }
+        // halfway to the edge is 45° for equidistant, and less for equisolid, which squeezes the border
+        let cam = FisheyeCamera::new(Vec3::ZERO, Vec3::Z, Vec3::Y, 1., 180., FisheyeProjection::Equidistant);
+        let d = cam.at(Vec2::new(0.75, 0.5), Vec2::ZERO).unwrap().direction;
+        assert!(d.dot(&Vec3::Z).a_eq(&std::f32::consts::FRAC_PI_4.cos()));
+        let cam = FisheyeCamera::new(Vec3::ZERO, Vec3::Z, Vec3::Y, 1., 180., FisheyeProjection::Equisolid);
+        let d = cam.at(Vec2::new(0.75, 0.5), Vec2::ZERO).unwrap().direction;
+        assert!(d.dot(&Vec3::Z) > std::f32::consts::FRAC_PI_4.cos());
+    }
+}
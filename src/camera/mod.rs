@@ -0,0 +1,365 @@
+use crate::ray::Ray;
+use crate::math::{Vec2, Vec3, Vector};
+use crate::sampling::distribution::Distribution2D;
+use crate::sampling::warp::{concentric_disk, uniform_triangle};
+use crate::world::World;
+
+pub mod builder;
+pub mod equirectangular;
+pub mod fisheye;
+pub mod cubemap;
+pub mod stereo;
+
+pub trait Camera: Sync {
+    // input: coords from (0,0) to (1,1), lens is a uniform sample for cameras with an aperture. None for parts of
+    // the image the camera doesn't cover, which stay black
+    fn at(&self, coords: Vec2, lens: Vec2) -> Option<Ray>;
+}
+
+fn prepare_input(coords: Vec2) -> Vec2 {
+    (coords - 0.5) * 2.
+}
+
+// normalized forward, up and right, with up made perpendicular to forward
+fn orthonormal_frame(forward: Vec3, up: Vec3) -> (Vec3, Vec3, Vec3) {
+    let f = forward.normalized();
+    let u = up.normalized();
+    debug_assert!(!f.is_nan());
+    debug_assert!(!u.is_nan());
+    let r = f.cross(&u).normalized();
+    (f, r.cross(&f).normalized(), r)
+}
+
+#[derive(Debug)]
+pub struct OrthographicCamera {
+    // Forward is normalized
+    // up and right are scaled to half width & height
+    origin: Vec3,
+    forward: Vec3,
+    up: Vec3,
+    right: Vec3,
+}
+
+impl OrthographicCamera {
+    pub fn new(origin: Vec3, forward: Vec3, up: Vec3, size: Vec2) -> Self {
+        let (f, u, r) = orthonormal_frame(forward, up);
+        Self {
+            origin,
+            forward: f,
+            right: r * size.x,
+            up: u * size.y,
+        }
+    }
+}
+
+impl Camera for OrthographicCamera {
+    fn at(&self, coords: Vec2, _lens: Vec2) -> Option<Ray> {
+        let c = prepare_input(coords);
+        Some(Ray::new(self.origin + self.up * -c.y + self.right * c.x, self.forward, None, None))
+    }
+}
+
+#[derive(Debug)]
+pub struct PerspectiveCamera {
+    // Forward is normalized
+    // up and right are scaled to half width & height of image plane at distance 1
+    origin: Vec3,
+    forward: Vec3,
+    up: Vec3,
+    right: Vec3,
+}
+
+impl PerspectiveCamera {
+    // fov in degrees across the width of the image
+    pub fn new(origin: Vec3, forward: Vec3, up: Vec3, aspect: f32, fov: f32) -> Self {
+        let (f, u, r) = orthonormal_frame(forward, up);
+        // opening angle between forward and view border
+        let alpha = fov / 180. * std::f32::consts::PI / 2.;
+        let size = Vec2::new(alpha.tan(), alpha.tan() / aspect);
+        Self {
+            origin,
+            forward: f,
+            right: r * size.x,
+            up: u * size.y,
+        }
+    }
+}
+
+impl PerspectiveCamera {
+    // direction through the image plane at distance 1, not normalized
+    fn direction(&self, coords: Vec2) -> Vec3 {
+        let c = prepare_input(coords);
+        self.forward + self.up * -c.y + self.right * c.x
+    }
+}
+
+impl PerspectiveCamera {
+    // distance along forward to whatever is visible at coords, None if the ray hits nothing
+    pub fn focus_distance(&self, world: &World, coords: Vec2) -> Option<f32> {
+        let hit = world.geometry.intersect(&Ray::new(self.origin, self.direction(coords), None, None))?;
+        let distance = (hit.point - self.origin).dot(&self.forward);
+        (distance > 0.).then_some(distance)
+    }
+}
+
+impl Camera for PerspectiveCamera {
+    fn at(&self, coords: Vec2, _lens: Vec2) -> Option<Ray> {
+        Some(Ray::new(self.origin, self.direction(coords), None, None))
+    }
+}
+
+// shape of the lens opening, which is the shape out of focus highlights take
+#[derive(Clone, Debug, PartialEq)]
+pub enum Aperture {
+    Circle,
+    // a regular polygon formed by the blades of the diaphragm, rotated counter clockwise in radians
+    Polygon {
+        blades: u32,
+        rotation: f32,
+    },
+    // a grayscale mask, rows from top to bottom, scaled so the longer side spans the lens
+    Mask {
+        distribution: Distribution2D,
+        size: Vec2,
+    },
+}
+
+impl Aperture {
+    pub fn polygon(blades: u32, rotation: f32) -> Self {
+        assert!(blades >= 3, "an aperture needs at least 3 blades");
+        Aperture::Polygon {
+            blades,
+            rotation,
+        }
+    }
+
+    // the values are how much light passes through each pixel. None if nothing does
+    pub fn mask(width: usize, height: usize, values: &[f32]) -> Option<Self> {
+        assert_eq!(values.len(), width * height);
+        if !values.iter().any(|&v| v > 0.) {
+            return None;
+        }
+        let longest = width.max(height) as f32;
+        Some(Aperture::Mask {
+            distribution: Distribution2D::new(values, width),
+            size: Vec2::new(width as f32 / longest, height as f32 / longest),
+        })
+    }
+
+    // maps a uniform sample onto the aperture inside the unit disk (or square for masks), x to the right and y up
+    pub fn sample(&self, u: Vec2) -> Vec2 {
+        match self {
+            Aperture::Circle => concentric_disk(u),
+            Aperture::Polygon { blades, rotation } => {
+                // pick one of the triangles between the center and two neighbouring corners
+                let n = *blades as f32;
+                let scaled = u.x * n;
+                let blade = scaled.floor().min(n - 1.);
+                let corner = |i: f32| {
+                    let angle = rotation + i / n * 2. * std::f32::consts::PI;
+                    Vec2::new(angle.cos(), angle.sin())
+                };
+                let b = uniform_triangle(Vec2::new(scaled - blade, u.y));
+                corner(blade) * b.x + corner(blade + 1.) * b.y
+            }
+            Aperture::Mask { distribution, size } => {
+                let (p, _) = distribution.sample(u);
+                Vec2::new(p.x * 2. - 1., 1. - p.y * 2.) * *size
+            }
+        }
+    }
+}
+
+// a perspective camera with a round lens instead of a pinhole. Everything at focus_distance (measured along forward)
+// is sharp, everything else is blurred the more the larger the aperture is
+#[derive(Debug)]
+pub struct ThinLensCamera {
+    pinhole: PerspectiveCamera,
+    aperture_radius: f32,
+    focus_distance: f32,
+    aperture: Aperture,
+    // how far the lens barrel is shifted towards the image border, in aperture radii at the border
+    cat_eye: f32,
+}
+
+impl ThinLensCamera {
+    pub fn new(pinhole: PerspectiveCamera, aperture_radius: f32, focus_distance: f32) -> Self {
+        debug_assert!(aperture_radius >= 0.);
+        debug_assert!(focus_distance > 0.);
+        ThinLensCamera {
+            pinhole,
+            aperture_radius,
+            focus_distance,
+            aperture: Aperture::Circle,
+            cat_eye: 0.,
+        }
+    }
+
+    pub fn with_aperture(mut self, aperture: Aperture) -> Self {
+        self.aperture = aperture;
+        self
+    }
+
+    // optical vignetting: towards the border of the image the lens barrel cuts off part of the aperture, so out of
+    // focus highlights turn into cat's eyes there
+    pub fn with_cat_eye(mut self, cat_eye: f32) -> Self {
+        debug_assert!(cat_eye >= 0.);
+        self.cat_eye = cat_eye;
+        self
+    }
+
+    pub fn focus_distance(&self) -> f32 {
+        self.focus_distance
+    }
+
+    // focuses on whatever is visible at coords. Returns the new focus distance, or None if the ray hits nothing and
+    // the focus is left unchanged
+    pub fn focus_on(&mut self, world: &World, coords: Vec2) -> Option<f32> {
+        let distance = self.pinhole.focus_distance(world, coords)?;
+        self.focus_distance = distance;
+        Some(distance)
+    }
+}
+
+impl ThinLensCamera {
+    // a point on the aperture in units of its radius, or None if the lens barrel cuts it off. Those samples stay
+    // black, which darkens the border of the image like the vignetting of a real lens
+    fn lens_point(&self, coords: Vec2, lens: Vec2) -> Option<Vec2> {
+        let p = self.aperture.sample(lens);
+        let c = prepare_input(coords);
+        let barrel = Vec2::new(c.x, -c.y) * self.cat_eye;
+        ((p - barrel).length() <= 1.).then_some(p)
+    }
+}
+
+impl Camera for ThinLensCamera {
+    fn at(&self, coords: Vec2, lens: Vec2) -> Option<Ray> {
+        let p = &self.pinhole;
+        let direction = p.direction(coords);
+        if self.aperture_radius == 0. {
+            return Some(Ray::new(p.origin, direction, None, None));
+        }
+        // all rays through the lens meet where the pinhole ray crosses the plane of focus
+        let focus = p.origin + direction * (self.focus_distance / direction.dot(&p.forward));
+        let right = p.right.normalized();
+        let up = right.cross(&p.forward);
+        let l = self.lens_point(coords, lens)? * self.aperture_radius;
+        let origin = p.origin + right * l.x + up * l.y;
+        Some(Ray::new(origin, focus - origin, None, None))
+    }
+}
+
+#[cfg(test)]
+mod camera_tests {
+    use crate::camera::{Aperture, Camera, OrthographicCamera, PerspectiveCamera, ThinLensCamera};
+    use crate::geometry::Sphere;
+    use crate::groups::GroupContent;
+    use crate::groups::bvh_group::BvhGroup;
+    use crate::math::{ApproxEq, Vec2, Vec3, Vector};
+    use crate::sampling::Sampler;
+    use crate::world::World;
+
+    #[test]
+    fn create_orthographic() {
+        let cam1 = OrthographicCamera::new(Vec3::new(1., 1., 1.), Vec3::new(-4., 0., 0.), Vec3::new(0., 90.3,  0.), Vec2::new(15., 15.));
+        assert!(cam1.forward.a_eq(&Vec3::new(-1., 0., 0.)));
+        assert!(cam1.origin.a_eq(&Vec3::new(1., 1., 1.)));
+        assert!(cam1.up.a_eq(&Vec3::new(0., 15., 0.)));
+        assert!(cam1.right.a_eq(&Vec3::new(0., 0., -15.)))
+    }
+
+    #[test]
+    fn thin_lens_focus() {
+        let pinhole = PerspectiveCamera::new(Vec3::ZERO, Vec3::Z, Vec3::Y, 1.5, 60.);
+        let cam = ThinLensCamera::new(PerspectiveCamera::new(Vec3::ZERO, Vec3::Z, Vec3::Y, 1.5, 60.), 0.5, 4.);
+        let mut sampler = Sampler::new(7, 0);
+        for _ in 0..100 {
+            let coords = sampler.next_2d();
+            let expected = pinhole.at(coords, Vec2::ZERO).unwrap();
+            let ray = cam.at(coords, sampler.next_2d()).unwrap();
+            assert!(ray.origin.z.a_eq(&0.));
+            assert!(ray.origin.length() <= 0.5 + 1e-5);
+            // every lens position sees the same point on the plane of focus
+            let focus = ray.at((4. - ray.origin.z) / ray.direction.z);
+            assert!(focus.a_eq(&expected.at(4. / expected.direction.z)), "{:?}", focus);
+        }
+        // without an aperture it is a pinhole camera
+        let cam = ThinLensCamera::new(PerspectiveCamera::new(Vec3::ZERO, Vec3::Z, Vec3::Y, 1.5, 60.), 0., 4.);
+        let coords = Vec2::new(0.2, 0.7);
+        assert!(cam.at(coords, Vec2::new(0.9, 0.1)).unwrap().direction.a_eq(&pinhole.at(coords, Vec2::ZERO).unwrap().direction));
+    }
+
+    #[test]
+    fn autofocus() {
+        let mut world = World::new(Box::new(BvhGroup::new()));
+        world.geometry.push(GroupContent::new(Box::new(Sphere::new(Vec3::new(0., 0., 7.), 1.)), None));
+        world.geometry.build();
+        let mut cam = ThinLensCamera::new(PerspectiveCamera::new(Vec3::ZERO, Vec3::Z, Vec3::Y, 1., 60.), 0.1, 1.);
+        assert!(cam.focus_on(&world, Vec2::new(0.5, 0.5)).unwrap().a_eq(&6.));
+        assert!(cam.focus_distance().a_eq(&6.));
+        // nothing in the corner, so the focus stays
+        assert!(cam.focus_on(&world, Vec2::ZERO).is_none());
+        assert!(cam.focus_distance().a_eq(&6.));
+    }
+
+    #[test]
+    fn aperture_shapes() {
+        let mut sampler = Sampler::new(2, 0);
+        let square = Aperture::polygon(4, std::f32::consts::FRAC_PI_4);
+        let half = 0.5f32.sqrt();
+        let mut mask = vec![0.; 16];
+        // only the second pixel of the top row lets light through
+        mask[1] = 0.7;
+        let mask = Aperture::mask(4, 4, &mask).unwrap();
+        let mut quadrants = [0; 4];
+        for _ in 0..1000 {
+            let u = sampler.next_2d();
+            let p = square.sample(u);
+            assert!(p.x.abs() <= half + 1e-5 && p.y.abs() <= half + 1e-5, "{:?}", p);
+            quadrants[(p.x > 0.) as usize + 2 * (p.y > 0.) as usize] += 1;
+            let p = mask.sample(u);
+            assert!((-0.5..=0.).contains(&p.x) && (0.5..=1.).contains(&p.y), "{:?}", p);
+        }
+        // the blades are sampled evenly
+        assert!(quadrants.iter().all(|&q| (200..300).contains(&q)), "{:?}", quadrants);
+        assert!(Aperture::mask(2, 1, &[0., 0.]).is_none());
+    }
+
+    #[test]
+    fn cat_eye() {
+        let cam = ThinLensCamera::new(PerspectiveCamera::new(Vec3::ZERO, Vec3::Z, Vec3::Y, 1., 60.), 1., 4.)
+            .with_cat_eye(1.);
+        let mut sampler = Sampler::new(5, 0);
+        let n = 200000;
+        let (mut passed, mut middle, mut edge) = (0, 0, 0);
+        for _ in 0..n {
+            // the right border of the image, the barrel is shifted by a whole radius
+            let Some(p) = cam.lens_point(Vec2::new(1., 0.5), sampler.next_2d()) else {
+                continue;
+            };
+            passed += 1;
+            assert!(p.length() <= 1. + 1e-5);
+            assert!((p - Vec2::new(1., 0.)).length() <= 1. + 1e-5, "{:?}", p);
+            // two boxes of the same size inside the overlap, one in the middle and one along the cut
+            if p.y.abs() < 0.15 {
+                if (0.425..0.575).contains(&p.x) {
+                    middle += 1;
+                } else if (0.05..0.2).contains(&p.x) {
+                    edge += 1;
+                }
+            }
+        }
+        // the overlap of two unit circles one radius apart
+        let overlap = 2. * std::f32::consts::FRAC_PI_3 - 0.75f32.sqrt();
+        let fraction = passed as f32 / n as f32;
+        assert!((fraction - overlap / std::f32::consts::PI).abs() < 0.01, "{}", fraction);
+        // the density is uniform over the overlap
+        let expected = n as f32 * 0.045 / std::f32::consts::PI;
+        for count in [middle, edge] {
+            assert!((count as f32 / expected - 1.).abs() < 0.06, "{} {} {}", middle, edge, expected);
+        }
+        // in the middle the whole aperture is used
+        assert_eq!(cam.lens_point(Vec2::from(0.5), Vec2::new(0.1, 0.5)), Some(Aperture::Circle.sample(Vec2::new(0.1, 0.5))));
+        assert!(cam.at(Vec2::new(1., 0.5), Vec2::new(0.01, 0.5)).is_none());
+    }
+}
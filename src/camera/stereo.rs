@@ -0,0 +1,170 @@
+use crate::camera::{Camera, PerspectiveCamera};
+use crate::camera::equirectangular::EquirectangularCamera;
+use crate::math::{Vec2, Vector};
+use crate::ray::Ray;
+
+// how the views of both eyes share the image. The left eye goes left or on top
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum StereoLayout {
+    SideBySide,
+    TopBottom,
+}
+
+impl StereoLayout {
+    // which eye sees coords, -1 for the left and 1 for the right one, and the coords within its view
+    fn split(&self, coords: Vec2) -> (f32, Vec2) {
+        match self {
+            StereoLayout::SideBySide if coords.x < 0.5 => (-1., Vec2::new(coords.x * 2., coords.y)),
+            StereoLayout::SideBySide => (1., Vec2::new(coords.x * 2. - 1., coords.y)),
+            StereoLayout::TopBottom if coords.y < 0.5 => (-1., Vec2::new(coords.x, coords.y * 2.)),
+            StereoLayout::TopBottom => (1., Vec2::new(coords.x, coords.y * 2. - 1.)),
+        }
+    }
+
+    // resolution of one eye's view in an image with the given resolution
+    pub fn eye_resolution(&self, resolution: (u32, u32)) -> (u32, u32) {
+        match self {
+            StereoLayout::SideBySide => (resolution.0 / 2, resolution.1),
+            StereoLayout::TopBottom => (resolution.0, resolution.1 / 2),
+        }
+    }
+}
+
+// two perspective cameras next to each other. Instead of turning the eyes inwards, which distorts the views
+// vertically, their image planes are shifted so that both see the same image at the convergence distance
+#[derive(Debug)]
+pub struct StereoCamera {
+    // the view from between the eyes
+    center: PerspectiveCamera,
+    interocular: f32,
+    // distance along forward at which both eyes agree, infinite for parallel views
+    convergence: f32,
+    layout: StereoLayout,
+}
+
+impl StereoCamera {
+    pub fn new(center: PerspectiveCamera, interocular: f32, convergence: f32, layout: StereoLayout) -> Self {
+        debug_assert!(interocular >= 0.);
+        debug_assert!(convergence > 0.);
+        StereoCamera {
+            center,
+            interocular,
+            convergence,
+            layout,
+        }
+    }
+}
+
+impl Camera for StereoCamera {
+    fn at(&self, coords: Vec2, _lens: Vec2) -> Option<Ray> {
+        let (eye, coords) = self.layout.split(coords);
+        let c = &self.center;
+        let offset = c.right.normalized() * (eye * self.interocular / 2.);
+        Some(Ray::new(c.origin + offset, c.direction(coords) - offset / self.convergence, None, None))
+    }
+}
+
+// omni-directional stereo: a 360° panorama per eye, with the eyes on a circle around the origin. Every column is
+// seen with the eyes placed for looking in its direction, so the panorama can be looked around in a headset
+#[derive(Debug)]
+pub struct OmniStereoCamera {
+    panorama: EquirectangularCamera,
+    interocular: f32,
+    // distance at which both eyes agree, infinite for parallel rays
+    convergence: f32,
+    layout: StereoLayout,
+}
+
+impl OmniStereoCamera {
+    pub fn new(panorama: EquirectangularCamera, interocular: f32, convergence: f32, layout: StereoLayout) -> Self {
+        debug_assert!(interocular >= 0.);
+        debug_assert!(convergence > 0.);
+        OmniStereoCamera {
+            panorama,
+            interocular,
+            convergence,
+            layout,
+        }
+    }
+}
+
+impl Camera for OmniStereoCamera {
+    fn at(&self, coords: Vec2, _lens: Vec2) -> Option<Ray> {
+        let (eye, coords) = self.layout.split(coords);
+        let p = &self.panorama;
+        let direction = p.direction(coords);
+        // to the right of the horizontal part of the direction. The eyes move together towards the poles, where
+        // there is no horizontal direction to place them along
+        let phi = (coords.x - 0.5) * 2. * std::f32::consts::PI;
+        let sideways = p.right * phi.cos() - p.forward * phi.sin();
+        let offset = sideways * (eye * self.interocular / 2. * (coords.y * std::f32::consts::PI).sin());
+        let direction = if self.convergence.is_finite() { direction * self.convergence - offset } else { direction };
+        Some(Ray::new(p.origin + offset, direction, None, None))
+    }
+}
+
+#[cfg(test)]
+mod stereo_tests {
+    use crate::camera::{Camera, PerspectiveCamera};
+    use crate::camera::equirectangular::EquirectangularCamera;
+    use crate::camera::stereo::{OmniStereoCamera, StereoCamera, StereoLayout};
+    use crate::math::{ApproxEq, Vec2, Vec3, Vector};
+
+    #[test]
+    fn converging_eyes() {
+        let center = PerspectiveCamera::new(Vec3::ZERO, Vec3::Z, Vec3::Y, 1., 60.);
+        let cam = StereoCamera::new(center, 0.1, 5., StereoLayout::SideBySide);
+        for coords in [Vec2::new(0.3, 0.4), Vec2::new(0.5, 0.5), Vec2::new(0.9, 0.1)] {
+            let left = cam.at(Vec2::new(coords.x / 2., coords.y), Vec2::ZERO).unwrap();
+            let right = cam.at(Vec2::new(coords.x / 2. + 0.5, coords.y), Vec2::ZERO).unwrap();
+            // right is forward x up, so -x for this camera
+            assert!(left.origin.a_eq(&Vec3::new(0.05, 0., 0.)));
+            assert!(right.origin.a_eq(&Vec3::new(-0.05, 0., 0.)));
+            // no vertical parallax
+            assert!((left.direction.y / left.direction.z).a_eq(&(right.direction.y / right.direction.z)));
+            // both eyes see the same point on the plane of convergence
+            assert!(left.at(5. / left.direction.z).a_eq(&right.at(5. / right.direction.z)));
+        }
+        // with the eyes on top of each other the halves are stacked
+        let center = PerspectiveCamera::new(Vec3::ZERO, Vec3::Z, Vec3::Y, 1., 60.);
+        let cam = StereoCamera::new(center, 0.1, f32::INFINITY, StereoLayout::TopBottom);
+        let top = cam.at(Vec2::new(0.5, 0.25), Vec2::ZERO).unwrap();
+        let bottom = cam.at(Vec2::new(0.5, 0.75), Vec2::ZERO).unwrap();
+        assert!(top.direction.a_eq(&Vec3::Z) && bottom.direction.a_eq(&Vec3::Z));
+        assert!(top.origin.a_eq(&-bottom.origin));
+        assert_eq!(StereoLayout::TopBottom.eye_resolution((200, 200)), (200, 100));
+        assert_eq!(StereoLayout::SideBySide.eye_resolution((200, 200)), (100, 200));
+    }
+
+    #[test]
+    fn omni_directional() {
+        let panorama = EquirectangularCamera::new(Vec3::new(0., 1., 0.), Vec3::Z, Vec3::Y);
+        let cam = OmniStereoCamera::new(panorama, 0.2, f32::INFINITY, StereoLayout::TopBottom);
+        for x in [0.1, 0.3, 0.5, 0.8] {
+            let left = cam.at(Vec2::new(x, 0.25), Vec2::ZERO).unwrap();
+            let right = cam.at(Vec2::new(x, 0.75), Vec2::ZERO).unwrap();
+            assert!(left.direction.a_eq(&right.direction));
+            // the eyes sit on a circle, perpendicular to the viewing direction
+            let between = right.origin - left.origin;
+            assert!(between.length().a_eq(&0.2));
+            assert!(between.dot(&left.direction).a_eq(&0.));
+            assert!(((left.origin + right.origin) / 2.).a_eq(&Vec3::new(0., 1., 0.)));
+        }
+        // looking forward the right eye is on the right
+        let right = cam.at(Vec2::new(0.5, 0.75), Vec2::ZERO).unwrap();
+        assert!(right.origin.a_eq(&Vec3::new(-0.1, 1., 0.)));
+        // straight up, in the top row of the right eye, both eyes are in the middle
+        let up = cam.at(Vec2::new(0.5, 0.5), Vec2::ZERO).unwrap();
+        assert!(up.origin.a_eq(&Vec3::new(0., 1., 0.)));
+        assert!(up.direction.a_eq(&Vec3::Y));
+        // converging rays meet
+        let panorama = EquirectangularCamera::new(Vec3::ZERO, Vec3::Z, Vec3::Y);
+        let cam = OmniStereoCamera::new(panorama, 0.2, 3., StereoLayout::SideBySide);
+        let left = cam.at(Vec2::new(0.2, 0.5), Vec2::ZERO).unwrap();
+        let right = cam.at(Vec2::new(0.7, 0.5), Vec2::ZERO).unwrap();
+        // the eyes are 0.1 to the side of the point 3 units ahead
+        let distance = (3f32 * 3. + 0.1 * 0.1).sqrt();
+        assert!(left.at(distance).a_eq(&right.at(distance)));
+        assert!(left.at(distance).length().a_eq(&3.));
+    }
+}
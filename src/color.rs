@@ -25,6 +25,15 @@ impl Color {
         ]
     }
 
+    pub fn max_component(&self) -> f32 {
+        self.r.max(self.g.max(self.b))
+    }
+
+    // perceived brightness of linear sRGB
+    pub fn luminance(&self) -> f32 {
+        0.2126 * self.r + 0.7152 * self.g + 0.0722 * self.b
+    }
+
     pub const BLACK: Self = Color{
         r: 0.,
         g: 0.,
@@ -0,0 +1,159 @@
+use image::{ImageBuffer, Rgb, RgbImage};
+use crate::color::Color;
+use crate::filters::Filter;
+use crate::math::Vec2;
+
+// weighted sum of all samples that contributed to a pixel
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+struct Pixel {
+    sum: Color,
+    weight: f32,
+}
+
+impl Pixel {
+    fn color(&self) -> Color {
+        // negative filter lobes can cancel out all weight
+        if self.weight <= 0. {
+            Color::BLACK
+        } else {
+            self.sum / self.weight
+        }
+    }
+}
+
+pub struct Film {
+    resolution: (u32, u32),
+    pixels: Vec<Pixel>,
+}
+
+// a rectangular part of the film that can be filled independently. Samples near the border spread into the
+// neighbouring tiles, so it covers the pixels of its tile plus the filter radius
+pub struct FilmTile<'a> {
+    filter: &'a dyn Filter,
+    // inclusive lower and exclusive upper pixel bounds
+    min: (u32, u32),
+    max: (u32, u32),
+    pixels: Vec<Pixel>,
+}
+
+impl Film {
+    pub fn new(resolution: (u32, u32)) -> Self {
+        Film {
+            resolution,
+            pixels: vec![Pixel::default(); (resolution.0 * resolution.1) as usize],
+        }
+    }
+
+    // tile for all samples taken within the pixels [min, max)
+    pub fn tile<'a>(&self, min: (u32, u32), max: (u32, u32), filter: &'a dyn Filter) -> FilmTile<'a> {
+        let r = filter.radius();
+        // pixel x has its center at x + 0.5
+        let min = (
+            (min.0 as f32 - r.x).floor().max(0.) as u32,
+            (min.1 as f32 - r.y).floor().max(0.) as u32,
+        );
+        let max = (
+            ((max.0 as f32 + r.x).ceil() as u32).min(self.resolution.0),
+            ((max.1 as f32 + r.y).ceil() as u32).min(self.resolution.1),
+        );
+        FilmTile {
+            filter,
+            min,
+            max,
+            pixels: vec![Pixel::default(); ((max.0 - min.0) * (max.1 - min.1)) as usize],
+        }
+    }
+
+    // floating point addition isn't associative, so tiles have to be merged in a fixed order for reproducible images
+    pub fn merge(&mut self, tile: FilmTile) {
+        let width = tile.max.0 - tile.min.0;
+        for (i, pixel) in tile.pixels.into_iter().enumerate() {
+            let x = tile.min.0 + i as u32 % width;
+            let y = tile.min.1 + i as u32 / width;
+            let target = &mut self.pixels[(y * self.resolution.0 + x) as usize];
+            target.sum += pixel.sum;
+            target.weight += pixel.weight;
+        }
+    }
+
+    pub fn color(&self, x: u32, y: u32) -> Color {
+        self.pixels[(y * self.resolution.0 + x) as usize].color()
+    }
+
+    pub fn to_image(&self) -> RgbImage {
+        ImageBuffer::from_fn(self.resolution.0, self.resolution.1, |x, y| Rgb(self.color(x, y).to_u8()))
+    }
+}
+
+impl FilmTile<'_> {
+    // position in continuous pixel coordinates, (0, 0) is the upper left corner of the image
+    pub fn add_sample(&mut self, position: Vec2, color: Color) {
+        let r = self.filter.radius();
+        // range of pixels whose center is within the filter radius
+        let x0 = ((position.x - 0.5 - r.x).ceil().max(self.min.0 as f32)) as u32;
+        let y0 = ((position.y - 0.5 - r.y).ceil().max(self.min.1 as f32)) as u32;
+        let x1 = ((position.x - 0.5 + r.x).floor() + 1.).min(self.max.0 as f32).max(0.) as u32;
+        let y1 = ((position.y - 0.5 + r.y).floor() + 1.).min(self.max.1 as f32).max(0.) as u32;
+        let width = self.max.0 - self.min.0;
+        for y in y0..y1 {
+            for x in x0..x1 {
+                let weight = self.filter.eval(Vec2::new(x as f32 + 0.5 - position.x, y as f32 + 0.5 - position.y));
+                let pixel = &mut self.pixels[((y - self.min.1) * width + x - self.min.0) as usize];
+                pixel.sum += color * weight;
+                pixel.weight += weight;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod film_tests {
+    use crate::color::Color;
+    use crate::film::Film;
+    use crate::filters::box_filter::BoxFilter;
+    use crate::filters::Filter;
+    use crate::filters::gaussian::GaussianFilter;
+    use crate::filters::mitchell::MitchellFilter;
+    use crate::math::{ApproxEq, Vec2};
+    use crate::sampling::Sampler;
+
+    #[test]
+    fn constant_color() {
+        // whatever the filter, a constant signal has to be reconstructed exactly
+        let filters: [Box<dyn Filter>; 3] = [Box::new(BoxFilter::default()), Box::new(GaussianFilter::default()), Box::new(MitchellFilter::default())];
+        for filter in filters.iter() {
+            let mut film = Film::new((10, 6));
+            let mut sampler = Sampler::new(0, 0);
+            for (min, max) in [((0, 0), (5, 6)), ((5, 0), (10, 6))] {
+                let mut tile = film.tile(min, max, filter.as_ref());
+                for y in min.1..max.1 {
+                    for x in min.0..max.0 {
+                        for _ in 0..16 {
+                            let p = Vec2::new(x as f32, y as f32) + sampler.next_2d();
+                            tile.add_sample(p, Color::new(0.5, 0.25, 1.));
+                        }
+                    }
+                }
+                film.merge(tile);
+            }
+            for y in 0..6 {
+                for x in 0..10 {
+                    let c = film.color(x, y);
+                    assert!(c.r.a_eq(&0.5) && c.g.a_eq(&0.25) && c.b.a_eq(&1.), "{:?}", c);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn box_filter_stays_in_pixel() {
+        let filter = BoxFilter::default();
+        let mut film = Film::new((3, 3));
+        let mut tile = film.tile((0, 0), (3, 3), &filter);
+        tile.add_sample(Vec2::new(1.2, 1.7), Color::WHITE);
+        film.merge(tile);
+        assert_eq!(film.color(1, 1), Color::WHITE);
+        assert_eq!(film.color(0, 1), Color::BLACK);
+        assert_eq!(film.color(1, 2), Color::BLACK);
+    }
+}
@@ -0,0 +1,36 @@
+use crate::filters::Filter;
+use crate::math::Vec2;
+
+// every sample within the radius counts the same. With a radius of 0.5 this is a plain average per pixel
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct BoxFilter {
+    radius: Vec2,
+}
+
+impl BoxFilter {
+    pub fn new(radius: Vec2) -> Self {
+        BoxFilter {
+            radius
+        }
+    }
+}
+
+impl Default for BoxFilter {
+    fn default() -> Self {
+        Self::new(Vec2::new(0.5, 0.5))
+    }
+}
+
+impl Filter for BoxFilter {
+    fn radius(&self) -> Vec2 {
+        self.radius
+    }
+
+    fn eval(&self, offset: Vec2) -> f32 {
+        if offset.x.abs() <= self.radius.x && offset.y.abs() <= self.radius.y {
+            1.
+        } else {
+            0.
+        }
+    }
+}
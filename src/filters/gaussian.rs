@@ -0,0 +1,42 @@
+use crate::filters::Filter;
+use crate::math::Vec2;
+
+// gaussian bell, shifted down so it reaches zero at the radius
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct GaussianFilter {
+    radius: Vec2,
+    sigma: f32,
+}
+
+impl GaussianFilter {
+    pub fn new(radius: Vec2, sigma: f32) -> Self {
+        GaussianFilter {
+            radius,
+            sigma
+        }
+    }
+
+    fn gaussian(&self, x: f32) -> f32 {
+        (-x * x / (2. * self.sigma * self.sigma)).exp()
+    }
+}
+
+impl Default for GaussianFilter {
+    fn default() -> Self {
+        Self::new(Vec2::new(1.5, 1.5), 0.5)
+    }
+}
+
+impl Filter for GaussianFilter {
+    fn radius(&self) -> Vec2 {
+        self.radius
+    }
+
+    fn eval(&self, offset: Vec2) -> f32 {
+        if offset.x.abs() > self.radius.x || offset.y.abs() > self.radius.y {
+            return 0.;
+        }
+        (self.gaussian(offset.x) - self.gaussian(self.radius.x)).max(0.)
+            * (self.gaussian(offset.y) - self.gaussian(self.radius.y)).max(0.)
+    }
+}
@@ -0,0 +1,51 @@
+use std::f32::consts::PI;
+use crate::filters::Filter;
+use crate::math::Vec2;
+
+// windowed sinc filter. tau is the number of sinc lobes within the radius
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct LanczosFilter {
+    radius: Vec2,
+    tau: f32,
+}
+
+impl LanczosFilter {
+    pub fn new(radius: Vec2, tau: f32) -> Self {
+        LanczosFilter {
+            radius,
+            tau
+        }
+    }
+
+    // x normalized to [-1, 1]
+    fn windowed_sinc(&self, x: f32) -> f32 {
+        let x = x.abs();
+        if x > 1. {
+            return 0.;
+        }
+        sinc(x * self.tau) * sinc(x)
+    }
+}
+
+fn sinc(x: f32) -> f32 {
+    if x.abs() < 1e-5 {
+        return 1.;
+    }
+    (PI * x).sin() / (PI * x)
+}
+
+impl Default for LanczosFilter {
+    fn default() -> Self {
+        Self::new(Vec2::new(2., 2.), 2.)
+    }
+}
+
+impl Filter for LanczosFilter {
+    fn radius(&self) -> Vec2 {
+        self.radius
+    }
+
+    fn eval(&self, offset: Vec2) -> f32 {
+        self.windowed_sinc(offset.x / self.radius.x) * self.windowed_sinc(offset.y / self.radius.y)
+    }
+}
@@ -0,0 +1,55 @@
+use crate::math::Vec2;
+
+pub mod box_filter;
+pub mod tent;
+pub mod gaussian;
+pub mod mitchell;
+pub mod lanczos;
+
+// pixel reconstruction filter. Offsets are measured in pixels from the pixel center
+pub trait Filter: Send + Sync {
+    // the filter is zero outside of [-radius, radius] on both axes
+    fn radius(&self) -> Vec2;
+    fn eval(&self, offset: Vec2) -> f32;
+}
+
+#[cfg(test)]
+mod filter_tests {
+    use crate::filters::box_filter::BoxFilter;
+    use crate::filters::Filter;
+    use crate::filters::gaussian::GaussianFilter;
+    use crate::filters::lanczos::LanczosFilter;
+    use crate::filters::mitchell::MitchellFilter;
+    use crate::filters::tent::TentFilter;
+    use crate::math::Vec2;
+
+    fn all() -> Vec<Box<dyn Filter>> {
+        vec![
+            Box::new(BoxFilter::default()),
+            Box::new(TentFilter::default()),
+            Box::new(GaussianFilter::default()),
+            Box::new(MitchellFilter::default()),
+            Box::new(LanczosFilter::default()),
+        ]
+    }
+
+    #[test]
+    fn zero_outside_radius() {
+        for f in all() {
+            let r = f.radius();
+            assert_eq!(f.eval(Vec2::new(r.x + 0.01, 0.)), 0.);
+            assert_eq!(f.eval(Vec2::new(0., -r.y - 0.01)), 0.);
+            assert!(f.eval(Vec2::ZERO) > 0.);
+        }
+    }
+
+    #[test]
+    fn symmetric() {
+        for f in all() {
+            for offset in [Vec2::new(0.3, 0.1), Vec2::new(0.7, -0.4), Vec2::new(1.2, 0.9)] {
+                assert_eq!(f.eval(offset), f.eval(offset * -1.));
+                assert_eq!(f.eval(offset), f.eval(Vec2::new(-offset.x, offset.y)));
+            }
+        }
+    }
+}
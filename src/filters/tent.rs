@@ -0,0 +1,32 @@
+use crate::filters::Filter;
+use crate::math::Vec2;
+
+// linear falloff from the center to the radius
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TentFilter {
+    radius: Vec2,
+}
+
+impl TentFilter {
+    pub fn new(radius: Vec2) -> Self {
+        TentFilter {
+            radius
+        }
+    }
+}
+
+impl Default for TentFilter {
+    fn default() -> Self {
+        Self::new(Vec2::new(1., 1.))
+    }
+}
+
+impl Filter for TentFilter {
+    fn radius(&self) -> Vec2 {
+        self.radius
+    }
+
+    fn eval(&self, offset: Vec2) -> f32 {
+        (self.radius.x - offset.x.abs()).max(0.) * (self.radius.y - offset.y.abs()).max(0.)
+    }
+}
@@ -1,4 +1,7 @@
+use crate::color::Color;
 use crate::geometry::{Geometry, Hit};
+use crate::lights::LightSource;
+use crate::lights::mesh::MeshLight;
 use crate::math::{Vec3, Vector};
 use crate::ray::Ray;
 
@@ -16,6 +19,12 @@ impl Aabb {
         }
     }
 
+    // an inverted box, so that any union with it yields the other operand
+    pub const EMPTY: Self = Aabb {
+        min: Vec3 { x: f32::INFINITY, y: f32::INFINITY, z: f32::INFINITY },
+        max: Vec3 { x: f32::NEG_INFINITY, y: f32::NEG_INFINITY, z: f32::NEG_INFINITY },
+    };
+
     pub fn inner_does_intersect(&self, ray: &Ray) -> bool {
         // efficient slab algorithm
         let t0 = (self.min - ray.origin) * ray.recip_direction;
@@ -23,8 +32,49 @@ impl Aabb {
         let t_min = t0.min_vector(&t1);
         let t_max = t0.max_vector(&t1);
         let potential_hit_dist = t_max.min_component();
-        // only accept hits, when we are inside the box or in front of it
-        t_min.max_component() <= potential_hit_dist && potential_hit_dist >= 0.
+        // only accept hits, when we are inside the box or in front of it and not beyond the end of the ray
+        let entry_dist = t_min.max_component();
+        entry_dist <= potential_hit_dist && potential_hit_dist >= 0. && entry_dist <= ray.max_distance
+    }
+
+    pub fn union(&self, other: &Aabb) -> Aabb {
+        Aabb {
+            min: self.min.min_vector(&other.min),
+            max: self.max.max_vector(&other.max),
+        }
+    }
+
+    pub fn grow(&mut self, point: Vec3) {
+        self.min = self.min.min_vector(&point);
+        self.max = self.max.max_vector(&point);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.min.x > self.max.x || self.min.y > self.max.y || self.min.z > self.max.z
+    }
+
+    pub fn centroid(&self) -> Vec3 {
+        (self.min + self.max) * 0.5
+    }
+
+    pub fn surface_area(&self) -> f32 {
+        if self.is_empty() {
+            return 0.;
+        }
+        let d = self.max - self.min;
+        2. * (d.x * d.y + d.y * d.z + d.z * d.x)
+    }
+
+    // index of the axis along which the box is widest
+    pub fn longest_axis(&self) -> usize {
+        let d = self.max - self.min;
+        if d.x > d.y && d.x > d.z {
+            0
+        } else if d.y > d.z {
+            1
+        } else {
+            2
+        }
     }
 
 }
@@ -52,11 +102,17 @@ impl Geometry for Aabb {
         } else {
             2
         };
+        let normal = Vec3::AXES[axis] * -ray.direction.dot(&Vec3::AXES[axis]).signum();
         Some(
             Hit {
                 distance: potential_hit_dist,
                 point: ray.at(potential_hit_dist),
-                normal: Vec3::AXES[axis] * -ray.direction.dot(&Vec3::AXES[axis]).signum()
+                normal,
+                geometric_normal: normal,
+                material: None,
+                // rays starting inside the box don't hit it
+                front_face: true,
+                ..Hit::default()
             }
         )
     }
@@ -75,14 +131,39 @@ impl Geometry for Aabb {
     fn get_bounds(&self) -> Aabb {
         *self
     }
+
+    // two triangles per side, wound so that they emit outwards
+    fn light(&self, radiance: Color) -> Option<Box<dyn LightSource>> {
+        let mut triangles = Vec::with_capacity(12);
+        for axis in 0..3 {
+            let (u, v) = (Vec3::AXES[(axis + 1) % 3], Vec3::AXES[(axis + 2) % 3]);
+            let size = self.max - self.min;
+            let (du, dv) = (u * size[(axis + 1) % 3], v * size[(axis + 2) % 3]);
+            for (side, corner) in [self.min, self.min + Vec3::AXES[axis] * size[axis]].into_iter().enumerate() {
+                let [a, b, c, d] = [corner, corner + du, corner + du + dv, corner + dv];
+                // the normal of a, b, d is (d - a) x (b - a), which points along -axis
+                if side == 0 {
+                    triangles.extend([[a, b, d], [b, c, d]]);
+                } else {
+                    triangles.extend([[a, d, b], [b, d, c]]);
+                }
+            }
+        }
+        let light = MeshLight::new(triangles, radiance)?;
+        Some(Box::new(light))
+    }
 }
 
 #[cfg(test)]
 mod aabb_tests {
     use crate::geometry::aabb::Aabb;
-    use crate::geometry::Geometry;
+    use crate::color::Color;
+    use crate::geometry::{Geometry, Hit};
+    use crate::lights::irradiance;
+    use crate::lights::rectangle::RectangleLight;
     use crate::math::Vec3;
     use crate::ray::Ray;
+    use crate::sampling::Sampler;
 
     #[test]
     fn does_intersect1() {
@@ -101,4 +182,43 @@ mod aabb_tests {
         assert_eq!(box2.min.x, -1.);
     }
 
-}
\ No newline at end of file
+    #[test]
+    fn union() {
+        let box1 = Aabb::new(Vec3::new(-1., -1., -1.), Vec3::new(1., 1., 1.));
+        let box2 = Aabb::new(Vec3::new(0., 2., -3.), Vec3::new(0.5, 3., -2.));
+        let u = box1.union(&box2);
+        assert_eq!(u.min, Vec3::new(-1., -1., -3.));
+        assert_eq!(u.max, Vec3::new(1., 3., 1.));
+        assert_eq!(Aabb::EMPTY.union(&box1), box1);
+        assert_eq!(Aabb::EMPTY.surface_area(), 0.);
+        assert_eq!(box1.surface_area(), 24.);
+    }
+
+    #[test]
+    fn inner_does_intersect_respects_max_distance() {
+        let box1 = Aabb::new(Vec3::new(-1., -1., -1.), Vec3::new(1., 1., 1.));
+        let r1 = Ray::new(Vec3::new(-5., 0., 0.), Vec3::new(1., 0., 0.), None, Some(3.));
+        assert!(!box1.inner_does_intersect(&r1));
+        let r2 = Ray::new(Vec3::new(-5., 0., 0.), Vec3::new(1., 0., 0.), None, Some(4.5));
+        assert!(box1.inner_does_intersect(&r2));
+    }
+
+
+    #[test]
+    fn light() {
+        // only the side facing the point is visible, all sides are sampled with the same density
+        let box1 = Aabb::new(Vec3::new(-1., -1., -1.), Vec3::new(1., 1., 1.));
+        let light = box1.light(Color::WHITE).unwrap();
+        let side = RectangleLight::new(Vec3::new(1., -1., -1.), Vec3::new(0., 2., 0.), Vec3::new(0., 0., 2.), Color::WHITE);
+        let hit = Hit { point: Vec3::new(3., 0.2, 0.), normal: -Vec3::X, geometric_normal: -Vec3::X, ..Hit::default() };
+        let mut sampler = Sampler::new(0, 0);
+        for _ in 0..100 {
+            if let Some(s) = light.sample(&hit, sampler.next_2d()) {
+                assert!(s.direction.x < 0.);
+            }
+        }
+        let actual = irradiance(light.as_ref(), &hit, &mut sampler, 40000);
+        let expected = irradiance(&side, &hit, &mut sampler, 40000);
+        assert!((actual - expected).abs() < 0.02 * expected, "{} {}", actual, expected);
+    }
+}
@@ -0,0 +1,116 @@
+use std::sync::Arc;
+use crate::color::Color;
+use crate::geometry::{Aabb, Geometry, Hit};
+use crate::lights::LightSource;
+use crate::lights::transformed::TransformedLight;
+use crate::math::{Mat4, Transform};
+use crate::ray::Ray;
+
+// places shared geometry in the world. Rays are moved into object space instead of moving the geometry, so any
+// number of instances only costs one copy of it
+pub struct Instance {
+    geometry: Arc<dyn Geometry>,
+    // object to world
+    transform: Transform,
+}
+
+impl Instance {
+    // the geometry has to be built before it is shared, instances never build it. Building through one of several
+    // references isn't possible, so an unbuilt mesh trips its debug assertion on the first ray instead
+    pub fn new(geometry: Arc<dyn Geometry>, transform: Transform) -> Self {
+        Instance {
+            geometry,
+            transform,
+        }
+    }
+
+    // None if the matrix can't be inverted
+    pub fn from_matrix(geometry: Arc<dyn Geometry>, matrix: Mat4) -> Option<Self> {
+        Some(Self::new(geometry, Transform::new(matrix)?))
+    }
+
+    pub fn transform(&self) -> &Transform {
+        &self.transform
+    }
+}
+
+impl Geometry for Instance {
+    fn intersect(&self, ray: &Ray) -> Option<Hit> {
+        let inverse = self.transform.inverse();
+        let object_ray = inverse.ray(ray);
+        let hit = self.geometry.intersect(&object_ray)?;
+        Some(Hit {
+            point: self.transform.point(hit.point),
+            normal: self.transform.normal(hit.normal),
+            geometric_normal: self.transform.normal(hit.geometric_normal),
+            // the object ray is scaled along with the geometry
+            distance: hit.distance / inverse.ray_scale(ray.direction),
+            ..hit
+        })
+    }
+
+    fn does_intersect(&self, ray: &Ray) -> bool {
+        self.geometry.does_intersect(&self.transform.inverse().ray(ray))
+    }
+
+    fn get_bounds(&self) -> Aabb {
+        self.transform.aabb(&self.geometry.get_bounds())
+    }
+
+    fn light(&self, radiance: Color) -> Option<Box<dyn LightSource>> {
+        let light = self.geometry.light(radiance)?;
+        Some(Box::new(TransformedLight::new(light, self.transform)))
+    }
+}
+
+#[cfg(test)]
+mod instance_tests {
+    use std::f32::consts::FRAC_PI_4;
+    use std::sync::Arc;
+    use crate::geometry::{Aabb, Geometry, Instance, Sphere, TriangleMesh};
+    use crate::math::{ApproxEq, Transform, Vec3, Vector};
+    use crate::ray::Ray;
+
+    #[test]
+    fn rotated_box() {
+        let aabb = Arc::new(Aabb::new(Vec3::new(-1., -1., -1.), Vec3::new(1., 1., 1.)));
+        let instance = Instance::new(aabb, Transform::translate(Vec3::new(0., 0., 5.)) * Transform::rotate_y(FRAC_PI_4));
+        // the edge of the rotated cube points towards the ray
+        let ray = Ray::new(Vec3::new(0., 0., 0.), Vec3::Z, None, None);
+        let hit = instance.intersect(&ray).unwrap();
+        assert!(hit.distance.a_eq(&(5. - 2f32.sqrt())));
+        assert!(hit.point.a_eq(&ray.at(hit.distance)));
+        assert!(hit.normal.a_eq(&Vec3::new(1., 0., -1.).normalized()) || hit.normal.a_eq(&Vec3::new(-1., 0., -1.).normalized()));
+        let b = instance.get_bounds();
+        assert!(b.min.a_eq(&Vec3::new(-2f32.sqrt(), -1., 5. - 2f32.sqrt())));
+        // the ray passes the cube to the side
+        assert!(!instance.does_intersect(&Ray::new(Vec3::new(1.5, 0., 0.), Vec3::Z, None, None)));
+    }
+
+    #[test]
+    fn scaled_sphere() {
+        // an ellipsoid stretched along x
+        let sphere = Arc::new(Sphere::new(Vec3::ZERO, 1.));
+        let instance = Instance::new(sphere, Transform::scale(Vec3::new(3., 1., 1.)));
+        let hit = instance.intersect(&Ray::new(Vec3::new(-10., 0., 0.), Vec3::X, None, None)).unwrap();
+        assert!(hit.distance.a_eq(&7.));
+        assert!(hit.normal.a_eq(&-Vec3::X));
+        // max_distance is in world space as well
+        assert!(instance.intersect(&Ray::new(Vec3::new(-10., 0., 0.), Vec3::X, None, Some(6.9))).is_none());
+        // the normal is perpendicular to the stretched surface, not the scaled sphere normal
+        let p = Vec3::new(3. * 0.6, 0.8, 0.);
+        let hit = instance.intersect(&Ray::new(p * 2., -p, None, None)).unwrap();
+        assert!(hit.point.a_eq(&p));
+        assert!(hit.normal.a_eq(&Vec3::new(0.6 / 3., 0.8, 0.).normalized()));
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "has to be built")]
+    fn unbuilt_geometry() {
+        let mesh = TriangleMesh::new(vec![Vec3::ZERO, Vec3::X, Vec3::Y], vec![], vec![], vec![[0, 1, 2]]);
+        let mut instance = Instance::new(Arc::new(mesh), Transform::translate(Vec3::Z));
+        instance.build();
+        instance.intersect(&Ray::new(Vec3::new(0.2, 0.2, -1.), Vec3::Z, None, None));
+    }
+}
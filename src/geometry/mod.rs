@@ -1,23 +1,64 @@
 mod sphere;
 mod aabb;
 mod triangle;
+mod triangle_mesh;
+mod instance;
 
-use crate::math::Vec3;
+use crate::color::Color;
+use crate::lights::LightSource;
+use crate::materials::MaterialId;
+use crate::math::{EPSILON, Vec2, Vec3, Vector};
 use crate::ray::Ray;
 
 pub use sphere::Sphere;
 pub use aabb::Aabb;
 pub use triangle::Triangle;
+pub use triangle_mesh::TriangleMesh;
+pub use instance::Instance;
 
 #[derive(Copy, Clone, Debug, Default, PartialEq)]
 pub struct Hit {
     pub point: Vec3,
+    // shading normal, may be interpolated and differ from the actual surface
     pub normal: Vec3,
+    // normal of the surface itself, on the same side as the shading normal
+    pub geometric_normal: Vec3,
     pub distance: f32,
+    // set by the group the geometry was pushed into
+    pub material: Option<MaterialId>,
+    // the normal always points to the outside of the surface. front_face is false if the ray hit it from the inside
+    pub front_face: bool,
+    // index of the triangle within its mesh, 0 for other geometry
+    pub triangle: usize,
+    // weights of the second and third vertex of the triangle, the first one has 1 - u - v
+    pub barycentrics: Vec2,
 }
 
-pub trait Geometry {
+impl Hit {
+    // origin for rays leaving the surface in the given direction. It is moved off the surface along the geometric
+    // normal, interpolated normals would push it below the surface at the shadow terminator
+    pub fn spawn_point(&self, direction: Vec3) -> Vec3 {
+        if direction.dot(&self.geometric_normal) < 0. {
+            self.point - self.geometric_normal * EPSILON
+        } else {
+            self.point + self.geometric_normal * EPSILON
+        }
+    }
+
+    pub fn spawn_ray(&self, direction: Vec3) -> Ray {
+        Ray::new(self.spawn_point(direction), direction, None, None)
+    }
+}
+
+pub trait Geometry: Send + Sync {
     fn intersect(&self, ray: &Ray) -> Option<Hit>;
     fn does_intersect(&self, ray: &Ray) -> bool;
     fn get_bounds(&self) -> Aabb;
+    // prepares acceleration structures once the scene is assembled. called before the first intersection test
+    fn build(&mut self) {}
+    // a light source covering the surface, for geometry with an emissive material. None if the surface can't be
+    // sampled, it is then only seen by camera rays and in specular reflections
+    fn light(&self, _radiance: Color) -> Option<Box<dyn LightSource>> {
+        None
+    }
 }
\ No newline at end of file
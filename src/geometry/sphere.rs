@@ -1,4 +1,7 @@
+use crate::color::Color;
 use crate::geometry::{Aabb, Geometry, Hit};
+use crate::lights::LightSource;
+use crate::lights::sphere::SphereLight;
 use crate::math::{Vec3, Vector};
 use crate::ray::Ray;
 
@@ -17,9 +20,9 @@ impl Sphere {
     }
 }
 
-impl Geometry for Sphere {
-    fn intersect(&self, ray: &Ray) -> Option<Hit> {
-        // we don't accept inner hits
+impl Sphere {
+    // distance to the first intersection inside the ray's range. hits from the inside are accepted as well
+    fn hit_distance(&self, ray: &Ray) -> Option<f32> {
         // vector from the ray origin to the center of the sphere
         let d = ray.origin - self.center;
         // the distance along the ray
@@ -33,37 +36,32 @@ impl Geometry for Sphere {
         if height2 > radius2 {
             return None;
         }
-        // find the most backwards hit point on the ray. if it's behind the origin, disregard (inside or behind the sphere)
-        let hit_distance = distance - (radius2 - height2).sqrt();
-        if hit_distance > ray.min_distance && hit_distance < ray.max_distance {
-            let hit_point = ray.at(hit_distance);
-            Some(Hit{
-                point: hit_point,
-                normal: (hit_point - self.center).normalized(),
-                distance: hit_distance
-            })
-        } else {
-            None
-        }
+        // the ray enters the sphere at the near and leaves it at the far point.
+        // if the near point is behind the origin we are inside (or the sphere is behind us)
+        let half_chord = (radius2 - height2).sqrt();
+        [distance - half_chord, distance + half_chord].into_iter()
+            .find(|&t| t > ray.min_distance && t < ray.max_distance)
+    }
+}
+
+impl Geometry for Sphere {
+    fn intersect(&self, ray: &Ray) -> Option<Hit> {
+        let hit_distance = self.hit_distance(ray)?;
+        let hit_point = ray.at(hit_distance);
+        let normal = (hit_point - self.center).normalized();
+        Some(Hit{
+            point: hit_point,
+            normal,
+            geometric_normal: normal,
+            distance: hit_distance,
+            material: None,
+            front_face: ray.direction.dot(&normal) < 0.,
+            ..Hit::default()
+        })
     }
 
     fn does_intersect(&self, ray: &Ray) -> bool {
-        // we don't accept inner hits
-        // vector from the ray origin to the center of the sphere
-        let d = ray.origin - self.center;
-        // the distance along the ray
-        let distance = -d.dot(&ray.direction);
-        // projected onto the ray direction. This is the closest point to the center on the ray
-        let point = ray.at(distance);
-        // height of the closest point over the center of the sphere
-        let height2 = (self.center - point).length_squared();
-        let radius2 = self.radius * self.radius;
-        // nearest point outside of sphere: no hit
-        if height2 > radius2 {
-            return false;
-        }
-        let hit_distance = distance - (radius2 + height2).sqrt();
-        hit_distance > ray.min_distance && hit_distance < ray.max_distance
+        self.hit_distance(ray).is_some()
     }
 
     fn get_bounds(&self) -> Aabb {
@@ -72,12 +70,16 @@ impl Geometry for Sphere {
             max: self.center + self.radius
         }
     }
+
+    fn light(&self, radiance: Color) -> Option<Box<dyn LightSource>> {
+        Some(Box::new(SphereLight::new(self.center, self.radius, radiance)))
+    }
 }
 
 #[cfg(test)]
 mod vec3_tests {
     use crate::geometry::{Geometry, Sphere};
-    use crate::math::Vec3;
+    use crate::math::{ApproxEq, Vec3};
     use crate::ray::Ray;
 
     #[test]
@@ -94,4 +96,20 @@ mod vec3_tests {
         s.intersect(&ray1);
     }
 
+    #[test]
+    fn intersect_from_inside() {
+        let s = Sphere::new(Vec3::new(0., 0., 0.), 2.);
+        let outside = s.intersect(&Ray::new(Vec3::new(-5., 0., 0.), Vec3::new(1., 0., 0.), None, None)).unwrap();
+        assert!(outside.distance.a_eq(&3.));
+        assert!(outside.front_face);
+        assert!(outside.normal.a_eq(&Vec3::new(-1., 0., 0.)));
+        let inside = s.intersect(&Ray::new(Vec3::new(0., 0., 0.), Vec3::new(1., 0., 0.), None, None)).unwrap();
+        assert!(inside.distance.a_eq(&2.));
+        assert!(!inside.front_face);
+        // the normal still points outwards
+        assert!(inside.normal.a_eq(&Vec3::new(1., 0., 0.)));
+        assert!(s.does_intersect(&Ray::new(Vec3::new(0., 0., 0.), Vec3::new(0., 1., 0.), None, None)));
+        assert!(s.intersect(&Ray::new(Vec3::new(3., 0., 0.), Vec3::new(1., 0., 0.), None, None)).is_none());
+    }
+
 }
\ No newline at end of file
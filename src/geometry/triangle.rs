@@ -1,11 +1,16 @@
+use crate::color::Color;
 use crate::geometry::{Aabb, Geometry, Hit};
-use crate::math::{EPSILON, Vec3, Vector};
+use crate::lights::LightSource;
+use crate::lights::triangle::TriangleLight;
+use crate::math::{Vec2, Vec3, Vector};
 use crate::ray::Ray;
 
 pub struct Triangle {
     v0: Vec3,
     v1: Vec3,
     v2: Vec3,
+    // vertex normals for smooth shading
+    normals: Option<[Vec3; 3]>,
 }
 
 impl Triangle {
@@ -13,55 +18,123 @@ impl Triangle {
         Triangle {
             v0,
             v1,
-            v2
+            v2,
+            normals: None,
         }
     }
+
+    pub fn with_normals(v0: Vec3, v1: Vec3, v2: Vec3, normals: [Vec3; 3]) -> Triangle {
+        Triangle {
+            v0,
+            v1,
+            v2,
+            normals: Some(normals.map(normalize_vertex_normal)),
+        }
+    }
+}
+
+// Möller–Trumbore, returns the distance and the barycentric coordinates of v1 and v2
+pub(crate) fn intersect_triangle(ray: &Ray, v0: Vec3, v1: Vec3, v2: Vec3) -> Option<(f32, Vec2)> {
+    // edges 1 and 2 (v0 -> v1 & v1 -> v2)
+    let e1 = v1 - v0;
+    let e2 = v2 - v0;
+    // perpendicular vector to the ray and the first edge
+    let h = ray.direction.cross(&e2);
+    // project the perpendicular vector onto the second edge
+    let a = e1.dot(&h);
+    // if the projection is 0, the ray is parallel to the triangle. It scales with the size of the triangle, so a
+    // fixed epsilon would reject small triangles entirely
+    if a == 0. {
+        return None;
+    }
+    // prepare the inverse of the projection length for computations
+    let f = 1. / a;
+    // vector from the ray origin to the triangle origin
+    let s = ray.origin - v0;
+    let u = f * s.dot(&h);
+    if !(0. ..=1.0).contains(&u) {
+        return None;
+    }
+    let q = s.cross(&e1);
+    let v = f * ray.direction.dot(&q);
+    if v < 0. || u + v > 1. {
+        return None;
+    }
+    let t = f * e2.dot(&q);
+    if t > ray.min_distance && t < ray.max_distance {
+        Some((t, Vec2::new(u, v)))
+    } else {
+        None
+    }
+}
+
+// the winding order defines the outside
+pub(crate) fn face_normal(v0: Vec3, v1: Vec3, v2: Vec3) -> Vec3 {
+    (v2 - v0).cross(&(v1 - v0)).normalized()
+}
+
+// zero length normals stay 0 instead of turning into NaN, interpolate_normal() then uses the face normal
+pub(crate) fn normalize_vertex_normal(normal: Vec3) -> Vec3 {
+    let length_squared = normal.length_squared();
+    if length_squared > 0. && length_squared.is_finite() {
+        normal.normalized()
+    } else {
+        Vec3::ZERO
+    }
+}
+
+// returns the shading and geometric normal. The vertex normals decide which side is outside, so the face normal is
+// flipped onto their side if they disagree
+pub(crate) fn interpolate_normal(face: Vec3, normals: [Vec3; 3], barycentrics: Vec2) -> (Vec3, Vec3) {
+    // a vertex without a usable normal makes the whole triangle flat
+    if normals.iter().any(|n| n.length_squared() == 0.) {
+        return (face, face);
+    }
+    let b = barycentrics;
+    let n = normals[0] * (1. - b.x - b.y) + normals[1] * b.x + normals[2] * b.y;
+    // opposing vertex normals can cancel out
+    if !(n.length_squared() > 0. && n.length_squared().is_finite()) {
+        return (face, face);
+    }
+    let n = n.normalized();
+    (n, if n.dot(&face) < 0. { -face } else { face })
+}
+
+// builds the hit for a triangle. Without vertex normals the surface is flat shaded
+pub(crate) fn triangle_hit(ray: &Ray, t: f32, barycentrics: Vec2, face: Vec3, normals: Option<[Vec3; 3]>, triangle: usize) -> Hit {
+    let (normal, geometric_normal) = match normals {
+        Some(normals) => interpolate_normal(face, normals, barycentrics),
+        None => (face, face),
+    };
+    Hit {
+        point: ray.at(t),
+        distance: t,
+        normal,
+        geometric_normal,
+        material: None,
+        front_face: ray.direction.dot(&geometric_normal) < 0.,
+        triangle,
+        barycentrics,
+    }
 }
 
 impl Geometry for Triangle {
     fn intersect(&self, ray: &Ray) -> Option<Hit> {
-        // edges 1 and 2 (v0 -> v1 & v1 -> v2)
-        let e1 = self.v1 - self.v0;
-        let e2 = self.v2 - self.v0;
-        // perpendicular vector to the ray and the first edge
-        let h = ray.direction.cross(&e2);
-        // project the perpendicular vector onto the second edge
-        let a = e1.dot(&h);
-        // if that projection is too small, the ray is parallel to the triangle
-        if a.abs() < EPSILON {
-            return None;
-        }
-        // prepare the inverse of the projection length for computations
-        let f = 1. / a;
-        // vector from the ray origin to the triangle origin
-        let s = ray.origin - self.v0;
-        let u = f * s.dot(&h);
-        if !(0. ..=1.0).contains(&u) {
-            return None;
-        }
-        let q = s.cross(&e1);
-        let v = f * ray.direction.dot(&q);
-        if v < 0. || u + v > 1. {
-            return None;
-        }
-        let t = f * e2.dot(&q);
-        if t > ray.min_distance && t < ray.max_distance {
-            Some(
-                Hit {
-                    point: ray.at(t),
-                    distance: t,
-                    normal: e2.cross(&e1).normalized()
-                }
-            )
-        } else {
-            None
-        }
+        let (t, barycentrics) = intersect_triangle(ray, self.v0, self.v1, self.v2)?;
+        let face = face_normal(self.v0, self.v1, self.v2);
+        Some(triangle_hit(ray, t, barycentrics, face, self.normals, 0))
     }
 
     fn does_intersect(&self, ray: &Ray) -> bool {
         self.intersect(ray).is_some()
     }
 
+    // None without an area, which would have nothing to sample
+    fn light(&self, radiance: Color) -> Option<Box<dyn LightSource>> {
+        let light = TriangleLight::new(self.v0, self.v1, self.v2, radiance);
+        (light.area() > 0.).then(|| Box::new(light) as Box<dyn LightSource>)
+    }
+
     fn get_bounds(&self) -> Aabb {
         Aabb {
             min: self.v0.min_vector(&self.v1.min_vector(&self.v2)),
@@ -74,9 +147,10 @@ impl Geometry for Triangle {
 
 #[cfg(test)]
 mod vec3_tests {
+    use crate::color::Color;
     use crate::geometry::Geometry;
     use crate::geometry::triangle::Triangle;
-    use crate::math::Vec3;
+    use crate::math::{ApproxEq, Vec2, Vec3};
     use crate::ray::Ray;
 
     #[test]
@@ -87,4 +161,52 @@ mod vec3_tests {
         // assert!(t1.intersect(&r1).is_some());
     }
 
+    #[test]
+    fn smooth_normals() {
+        let n = [Vec3::new(-1., 0., 1.), Vec3::new(1., 0., 1.), Vec3::new(0., 1., 1.)];
+        let t = Triangle::with_normals(Vec3::new(0., 0., 0.), Vec3::new(1., 0., 0.), Vec3::new(0., 1., 0.), n);
+        let hit = t.intersect(&Ray::new(Vec3::new(0.5, 0., -1.), Vec3::new(0., 0., 1.), None, None)).unwrap();
+        // halfway between the first two vertices
+        assert!(hit.normal.a_eq(&Vec3::new(0., 0., 1.)));
+        assert_eq!(hit.barycentrics, Vec2::new(0.5, 0.));
+        // the face normal of this winding points to -z, but the vertex normals define the outside
+        assert_eq!(hit.geometric_normal, Vec3::new(0., 0., 1.));
+        assert!(!hit.front_face);
+        let flat = Triangle::new(Vec3::new(0., 0., 0.), Vec3::new(1., 0., 0.), Vec3::new(0., 1., 0.));
+        let hit = flat.intersect(&Ray::new(Vec3::new(0.5, 0.2, -1.), Vec3::new(0., 0., 1.), None, None)).unwrap();
+        assert_eq!(hit.normal, Vec3::new(0., 0., -1.));
+        assert_eq!(hit.normal, hit.geometric_normal);
+        assert!(hit.front_face);
+    }
+
+    #[test]
+    fn zero_vertex_normal() {
+        let n = [Vec3::new(0., 0., -1.), Vec3::ZERO, Vec3::new(0., 0., -1.)];
+        let t = Triangle::with_normals(Vec3::new(0., 0., 0.), Vec3::new(1., 0., 0.), Vec3::new(0., 1., 0.), n);
+        let hit = t.intersect(&Ray::new(Vec3::new(0.2, 0.2, -1.), Vec3::new(0., 0., 1.), None, None)).unwrap();
+        assert_eq!(hit.normal, Vec3::new(0., 0., -1.));
+        assert_eq!(hit.normal, hit.geometric_normal);
+    }
+
+    #[test]
+    fn degenerate_light() {
+        let line = Triangle::new(Vec3::new(0., 3., 0.), Vec3::new(1., 3., 0.), Vec3::new(2., 3., 0.));
+        assert!(line.light(Color::WHITE).is_none());
+        let flat = Triangle::new(Vec3::new(0., 3., 0.), Vec3::new(1., 3., 0.), Vec3::new(0., 3., 1.));
+        assert!(flat.light(Color::WHITE).is_some());
+    }
+
+    #[test]
+    fn spawn_offset() {
+        let n = [Vec3::new(-1., 0., -1.), Vec3::new(1., 0., -1.), Vec3::new(0., 1., -1.)];
+        let t = Triangle::with_normals(Vec3::new(0., 0., 0.), Vec3::new(1., 0., 0.), Vec3::new(0., 1., 0.), n);
+        let hit = t.intersect(&Ray::new(Vec3::new(0.2, 0.2, -1.), Vec3::new(0., 0., 1.), None, None)).unwrap();
+        // grazing directions above the shading normal but below the surface leave on the geometric side
+        let ray = hit.spawn_ray(Vec3::new(1., 0., 0.01));
+        assert!(ray.origin.z > 0.);
+        let ray = hit.spawn_ray(Vec3::new(1., 0., -0.01));
+        assert!(ray.origin.z < 0.);
+        assert!(t.intersect(&hit.spawn_ray(Vec3::new(0., 0., -1.))).is_none());
+    }
+
 }
\ No newline at end of file
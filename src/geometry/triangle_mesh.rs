@@ -0,0 +1,162 @@
+use crate::bvh::Bvh;
+use crate::color::Color;
+use crate::geometry::{Aabb, Geometry, Hit};
+use crate::geometry::triangle::{face_normal, intersect_triangle, normalize_vertex_normal, triangle_hit};
+use crate::lights::LightSource;
+use crate::lights::mesh::MeshLight;
+use crate::math::{Vec2, Vec3, Vector};
+use crate::ray::Ray;
+
+// triangles sharing a vertex buffer. Uses the same winding as Triangle: the normal is (v2 - v0) x (v1 - v0)
+pub struct TriangleMesh {
+    positions: Vec<Vec3>,
+    // per vertex, or empty
+    normals: Vec<Vec3>,
+    uvs: Vec<Vec2>,
+    indices: Vec<[u32; 3]>,
+    // empty until build() is called
+    bvh: Bvh,
+}
+
+impl TriangleMesh {
+    pub fn new(positions: Vec<Vec3>, normals: Vec<Vec3>, uvs: Vec<Vec2>, indices: Vec<[u32; 3]>) -> Self {
+        assert!(normals.is_empty() || normals.len() == positions.len(), "there has to be one normal per vertex");
+        assert!(uvs.is_empty() || uvs.len() == positions.len(), "there has to be one uv per vertex");
+        assert!(indices.iter().flatten().all(|&i| (i as usize) < positions.len()), "vertex index out of range");
+        TriangleMesh {
+            positions,
+            normals: normals.into_iter().map(normalize_vertex_normal).collect(),
+            uvs,
+            indices,
+            bvh: Bvh::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.indices.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    pub fn positions(&self) -> &[Vec3] {
+        &self.positions
+    }
+
+    pub fn normals(&self) -> &[Vec3] {
+        &self.normals
+    }
+
+    pub fn uvs(&self) -> &[Vec2] {
+        &self.uvs
+    }
+
+    pub fn indices(&self) -> &[[u32; 3]] {
+        &self.indices
+    }
+
+    pub fn vertices(&self, triangle: usize) -> [Vec3; 3] {
+        self.indices[triangle].map(|i| self.positions[i as usize])
+    }
+
+    fn triangle_bounds(&self, triangle: usize) -> Aabb {
+        let [v0, v1, v2] = self.vertices(triangle);
+        Aabb {
+            min: v0.min_vector(&v1.min_vector(&v2)),
+            max: v0.max_vector(&v1.max_vector(&v2)),
+        }
+    }
+}
+
+impl Geometry for TriangleMesh {
+    fn intersect(&self, ray: &Ray) -> Option<Hit> {
+        debug_assert!(self.is_empty() || !self.bvh.is_empty(), "TriangleMesh has to be built before intersecting");
+        // the normal is only computed for the closest hit
+        let (triangle, t, barycentrics) = self.bvh.intersect(ray, |i, ray| {
+            let [v0, v1, v2] = self.vertices(i);
+            intersect_triangle(ray, v0, v1, v2).map(|(t, uv)| (t, (i, t, uv)))
+        })?;
+        let [v0, v1, v2] = self.vertices(triangle);
+        let normals = (!self.normals.is_empty()).then(|| self.indices[triangle].map(|i| self.normals[i as usize]));
+        Some(triangle_hit(ray, t, barycentrics, face_normal(v0, v1, v2), normals, triangle))
+    }
+
+    fn does_intersect(&self, ray: &Ray) -> bool {
+        debug_assert!(self.is_empty() || !self.bvh.is_empty(), "TriangleMesh has to be built before intersecting");
+        self.bvh.does_intersect(ray, |i| {
+            let [v0, v1, v2] = self.vertices(i);
+            intersect_triangle(ray, v0, v1, v2).is_some()
+        })
+    }
+
+    fn get_bounds(&self) -> Aabb {
+        if self.bvh.is_empty() {
+            (0..self.len()).fold(Aabb::EMPTY, |b, i| b.union(&self.triangle_bounds(i)))
+        } else {
+            self.bvh.bounds()
+        }
+    }
+
+    // the vertex normals only affect shading, the light emits from the side of the face normal
+    fn light(&self, radiance: Color) -> Option<Box<dyn LightSource>> {
+        let light = MeshLight::new((0..self.len()).map(|i| self.vertices(i)), radiance)?;
+        Some(Box::new(light))
+    }
+
+    fn build(&mut self) {
+        let bounds: Vec<Aabb> = (0..self.len()).map(|i| self.triangle_bounds(i)).collect();
+        self.bvh = Bvh::build(&bounds);
+    }
+}
+
+#[cfg(test)]
+mod triangle_mesh_tests {
+    use crate::geometry::{Geometry, Triangle, TriangleMesh};
+    use crate::math::{ApproxEq, Vec3};
+    use crate::ray::Ray;
+    use crate::sampling::Sampler;
+
+    fn random_vec(sampler: &mut Sampler, scale: f32) -> Vec3 {
+        Vec3::new(sampler.next_f32() - 0.5, sampler.next_f32() - 0.5, sampler.next_f32() - 0.5) * scale
+    }
+
+    #[test]
+    fn matches_triangles() {
+        let mut sampler = Sampler::new(3, 0);
+        let positions: Vec<Vec3> = (0..300).map(|_| random_vec(&mut sampler, 10.)).collect();
+        let indices: Vec<[u32; 3]> = (0..500).map(|_| [0, 1, 2].map(|_| sampler.next_u32() % 300)).collect();
+        let mut mesh = TriangleMesh::new(positions.clone(), vec![], vec![], indices.clone());
+        mesh.build();
+        let triangles: Vec<Triangle> = indices.iter()
+            .map(|t| Triangle::new(positions[t[0] as usize], positions[t[1] as usize], positions[t[2] as usize]))
+            .collect();
+        for _ in 0..1000 {
+            let ray = Ray::new(random_vec(&mut sampler, 20.), random_vec(&mut sampler, 1.), None, None);
+            let expected = triangles.iter().enumerate()
+                .filter_map(|(i, t)| t.intersect(&ray).map(|h| (i, h)))
+                .min_by(|a, b| a.1.distance.total_cmp(&b.1.distance));
+            let actual = mesh.intersect(&ray);
+            assert_eq!(expected.map(|(_, h)| h.distance), actual.map(|h| h.distance));
+            assert_eq!(expected.is_some(), mesh.does_intersect(&ray));
+            if let (Some((i, expected)), Some(actual)) = (expected, actual) {
+                assert_eq!(i, actual.triangle);
+                assert_eq!(expected.normal, actual.normal);
+                // the barycentrics reconstruct the hit point
+                let [v0, v1, v2] = mesh.vertices(actual.triangle);
+                let b = actual.barycentrics;
+                let p = v0 * (1. - b.x - b.y) + v1 * b.x + v2 * b.y;
+                assert!(p.a_eq(&actual.point), "{:?} {:?}", p, actual.point);
+            }
+        }
+    }
+
+    #[test]
+    fn tiny_triangles() {
+        // the parallel test must not depend on the size of the triangle
+        let mut mesh = TriangleMesh::new(vec![Vec3::new(0., 0., 0.), Vec3::new(1e-4, 0., 0.), Vec3::new(0., 1e-4, 0.)], vec![], vec![], vec![[0, 1, 2]]);
+        mesh.build();
+        let hit = mesh.intersect(&Ray::new(Vec3::new(2e-5, 2e-5, -1.), Vec3::Z, None, None)).unwrap();
+        assert!(hit.distance.a_eq(&1.));
+    }
+}
@@ -0,0 +1,129 @@
+use crate::bvh::Bvh;
+use crate::geometry::{Aabb, Geometry, Hit};
+use crate::groups::{Group, GroupContent};
+use crate::ray::Ray;
+
+#[derive(Default)]
+pub struct BvhGroup {
+    list: Vec<GroupContent>,
+    // empty until build() is called
+    bvh: Bvh,
+}
+
+impl BvhGroup {
+    pub fn new() -> Self {
+        Self {
+            list: Vec::new(),
+            bvh: Bvh::new(),
+        }
+    }
+}
+
+impl Group for BvhGroup {
+    fn push(&mut self, item: GroupContent) {
+        self.list.push(item);
+        // the tree is no longer valid and has to be rebuilt
+        self.bvh = Bvh::new();
+    }
+}
+
+impl Geometry for BvhGroup {
+    fn intersect(&self, ray: &Ray) -> Option<Hit> {
+        debug_assert!(self.list.is_empty() || !self.bvh.is_empty(), "BvhGroup has to be built before intersecting");
+        self.bvh.intersect(ray, |i, ray| self.list[i].intersect(ray).map(|hit| (hit.distance, hit)))
+    }
+
+    fn does_intersect(&self, ray: &Ray) -> bool {
+        debug_assert!(self.list.is_empty() || !self.bvh.is_empty(), "BvhGroup has to be built before intersecting");
+        self.bvh.does_intersect(ray, |i| self.list[i].item.does_intersect(ray))
+    }
+
+    fn get_bounds(&self) -> Aabb {
+        if self.bvh.is_empty() {
+            self.list.iter().fold(Aabb::EMPTY, |b, g| b.union(&g.item.get_bounds()))
+        } else {
+            self.bvh.bounds()
+        }
+    }
+
+    fn build(&mut self) {
+        for g in &mut self.list {
+            g.item.build();
+        }
+        let bounds: Vec<Aabb> = self.list.iter().map(|g| g.item.get_bounds()).collect();
+        self.bvh = Bvh::build(&bounds);
+    }
+}
+
+#[cfg(test)]
+mod bvh_group_tests {
+    use crate::geometry::{Aabb, Geometry, Sphere, Triangle};
+    use crate::groups::{Group, GroupContent};
+    use crate::groups::bvh_group::BvhGroup;
+    use crate::groups::simple_group::SimpleGroup;
+    use crate::math::Vec3;
+    use crate::ray::Ray;
+
+    // small deterministic generator, so the tests don't need any dependencies
+    struct Lcg(u32);
+
+    impl Lcg {
+        fn next(&mut self) -> f32 {
+            self.0 = self.0.wrapping_mul(1664525).wrapping_add(1013904223);
+            (self.0 >> 8) as f32 / (1 << 24) as f32
+        }
+
+        fn vec(&mut self, scale: f32) -> Vec3 {
+            Vec3::new(self.next() - 0.5, self.next() - 0.5, self.next() - 0.5) * scale
+        }
+    }
+
+    fn fill(group: &mut dyn Group, seed: u32) {
+        let mut rng = Lcg(seed);
+        for _ in 0..200 {
+            let c = rng.vec(20.);
+            group.push(GroupContent::new(Box::new(Sphere::new(c, rng.next() * 0.8 + 0.1)), None));
+            let v0 = rng.vec(20.);
+            group.push(GroupContent::new(Box::new(Triangle::new(v0, v0 + rng.vec(3.), v0 + rng.vec(3.))), None));
+            let min = rng.vec(20.);
+            group.push(GroupContent::new(Box::new(Aabb::new(min, min + rng.vec(2.))), None));
+        }
+    }
+
+    #[test]
+    fn matches_simple_group() {
+        let mut simple = SimpleGroup::new();
+        let mut bvh = BvhGroup::new();
+        fill(&mut simple, 7);
+        fill(&mut bvh, 7);
+        bvh.build();
+        let mut rng = Lcg(42);
+        for _ in 0..2000 {
+            let ray = Ray::new(rng.vec(40.), rng.vec(1.), None, None);
+            let expected = simple.intersect(&ray);
+            let actual = bvh.intersect(&ray);
+            assert_eq!(expected.map(|h| h.distance), actual.map(|h| h.distance));
+            assert_eq!(expected.is_some(), bvh.does_intersect(&ray));
+        }
+    }
+
+    #[test]
+    fn bounds() {
+        let mut bvh = BvhGroup::new();
+        bvh.push(GroupContent::new(Box::new(Sphere::new(Vec3::new(0., 0., 0.), 1.)), None));
+        bvh.push(GroupContent::new(Box::new(Sphere::new(Vec3::new(5., 0., 0.), 2.)), None));
+        bvh.build();
+        let b = bvh.get_bounds();
+        assert_eq!(b.min, Vec3::new(-1., -2., -2.));
+        assert_eq!(b.max, Vec3::new(7., 2., 2.));
+    }
+
+    #[test]
+    fn empty() {
+        let mut bvh = BvhGroup::new();
+        bvh.build();
+        let ray = Ray::new(Vec3::new(0., 0., 0.), Vec3::new(1., 0., 0.), None, None);
+        assert!(bvh.intersect(&ray).is_none());
+        assert!(!bvh.does_intersect(&ray));
+    }
+}
@@ -1,22 +1,42 @@
-use crate::geometry::{Geometry, Sphere};
-use crate::materials::Material;
+use crate::geometry::{Geometry, Hit};
+use crate::materials::MaterialId;
+use crate::ray::Ray;
 
 pub mod simple_group;
+pub mod bvh_group;
 
-pub struct GroupContent<'a> {
+pub struct GroupContent {
     item: Box<dyn Geometry>,
-    material: Option<&'a Box<dyn Material>>
+    // handle into World::materials. None keeps the material bound further down (or the default)
+    material: Option<MaterialId>
 }
 
-impl<'a> GroupContent<'a> {
-    pub(crate) fn new(item: Box<dyn Geometry>, material: Option<&Box<dyn Material>>) -> GroupContent {
+impl GroupContent {
+    pub(crate) fn new(item: Box<dyn Geometry>, material: Option<MaterialId>) -> GroupContent {
         GroupContent {
             item,
             material
         }
     }
+
+    // groups are geometry as well, so they can be nested to organise a scene hierarchically
+    pub(crate) fn group(item: Box<dyn Group>, material: Option<MaterialId>) -> GroupContent {
+        GroupContent {
+            item,
+            material
+        }
+    }
+
+    pub(crate) fn intersect(&self, ray: &Ray) -> Option<Hit> {
+        let mut hit = self.item.intersect(ray)?;
+        // materials bound to nested items take precedence
+        if hit.material.is_none() {
+            hit.material = self.material;
+        }
+        Some(hit)
+    }
 }
 
 pub trait Group: Geometry {
     fn push(&mut self, item: GroupContent);
-}
\ No newline at end of file
+}
@@ -2,30 +2,39 @@ use crate::geometry::{Aabb, Geometry, Hit};
 use crate::groups::{Group, GroupContent};
 use crate::ray::Ray;
 
-#[derive(Default)]
-pub struct SimpleGroup<'a> {
-    list: Vec<GroupContent<'a>>
+pub struct SimpleGroup {
+    list: Vec<GroupContent>,
+    // union of the bounds of all items, updated on every push
+    bounds: Aabb,
 }
 
-impl<'a> SimpleGroup<'a> {
+impl SimpleGroup {
     pub fn new() -> Self {
         Self {
-            list: Vec::new()
+            list: Vec::new(),
+            bounds: Aabb::EMPTY,
         }
     }
 }
 
-impl<'a> Group for SimpleGroup<'a> {
+impl Default for SimpleGroup {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Group for SimpleGroup {
     fn push(&mut self, item: GroupContent) {
+        self.bounds = self.bounds.union(&item.item.get_bounds());
         self.list.push(item)
     }
 }
 
-impl<'a> Geometry for SimpleGroup<'a> {
+impl Geometry for SimpleGroup {
     fn intersect(&self, ray: &Ray) -> Option<Hit> {
         let mut hit: Option<Hit> = None;
         for g in &self.list {
-            if let Some(new_hit) = g.item.intersect(ray) {
+            if let Some(new_hit) = g.intersect(ray) {
                 if let Some(old_hit) = hit {
                     if new_hit.distance < old_hit.distance {
                         hit = Some(new_hit);
@@ -43,6 +52,77 @@ impl<'a> Geometry for SimpleGroup<'a> {
     }
 
     fn get_bounds(&self) -> Aabb {
-        todo!()
+        self.bounds
+    }
+
+    fn build(&mut self) {
+        for g in &mut self.list {
+            g.item.build();
+        }
+        // building may have changed the bounds of nested items
+        self.bounds = self.list.iter().fold(Aabb::EMPTY, |b, g| b.union(&g.item.get_bounds()));
+    }
+}
+
+#[cfg(test)]
+mod simple_group_tests {
+    use crate::geometry::{Geometry, Sphere};
+    use crate::groups::{Group, GroupContent};
+    use crate::groups::bvh_group::BvhGroup;
+    use crate::groups::simple_group::SimpleGroup;
+    use crate::materials::MaterialId;
+    use crate::math::Vec3;
+    use crate::ray::Ray;
+
+    #[test]
+    fn get_bounds() {
+        let mut group = SimpleGroup::new();
+        assert!(group.get_bounds().is_empty());
+        group.push(GroupContent::new(Box::new(Sphere::new(Vec3::new(0., 0., 0.), 1.)), None));
+        assert_eq!(group.get_bounds().min, Vec3::new(-1., -1., -1.));
+        group.push(GroupContent::new(Box::new(Sphere::new(Vec3::new(3., 0., 0.), 1.)), None));
+        assert_eq!(group.get_bounds().min, Vec3::new(-1., -1., -1.));
+        assert_eq!(group.get_bounds().max, Vec3::new(4., 1., 1.));
+    }
+
+    #[test]
+    fn nested() {
+        let mut inner = SimpleGroup::new();
+        inner.push(GroupContent::new(Box::new(Sphere::new(Vec3::new(10., 0., 0.), 1.)), None));
+        let mut inner_bvh = BvhGroup::new();
+        inner_bvh.push(GroupContent::new(Box::new(Sphere::new(Vec3::new(0., 10., 0.), 1.)), None));
+
+        let mut outer = BvhGroup::new();
+        outer.push(GroupContent::group(Box::new(inner), None));
+        outer.push(GroupContent::group(Box::new(inner_bvh), None));
+        outer.push(GroupContent::new(Box::new(Sphere::new(Vec3::new(0., 0., 10.), 1.)), None));
+        outer.build();
+
+        let b = outer.get_bounds();
+        assert_eq!(b.min, Vec3::new(-1., -1., -1.));
+        assert_eq!(b.max, Vec3::new(11., 11., 11.));
+        for target in [Vec3::new(10., 0., 0.), Vec3::new(0., 10., 0.), Vec3::new(0., 0., 10.)] {
+            let hit = outer.intersect(&Ray::new(Vec3::ZERO, target, None, None));
+            assert_eq!(hit.map(|h| h.distance), Some(9.));
+        }
+        assert!(outer.intersect(&Ray::new(Vec3::ZERO, Vec3::new(1., 1., 1.), None, None)).is_none());
+    }
+
+    #[test]
+    fn material_binding() {
+        let mut inner = SimpleGroup::new();
+        inner.push(GroupContent::new(Box::new(Sphere::new(Vec3::new(10., 0., 0.), 1.)), Some(MaterialId(1))));
+        inner.push(GroupContent::new(Box::new(Sphere::new(Vec3::new(0., 10., 0.), 1.)), None));
+        let mut outer = SimpleGroup::new();
+        outer.push(GroupContent::group(Box::new(inner), Some(MaterialId(2))));
+        outer.push(GroupContent::new(Box::new(Sphere::new(Vec3::new(0., 0., 10.), 1.)), None));
+
+        // the innermost binding wins, unbound items inherit from their parent
+        let hit = outer.intersect(&Ray::new(Vec3::ZERO, Vec3::X, None, None)).unwrap();
+        assert_eq!(hit.material, Some(MaterialId(1)));
+        let hit = outer.intersect(&Ray::new(Vec3::ZERO, Vec3::Y, None, None)).unwrap();
+        assert_eq!(hit.material, Some(MaterialId(2)));
+        let hit = outer.intersect(&Ray::new(Vec3::ZERO, Vec3::Z, None, None)).unwrap();
+        assert_eq!(hit.material, None);
     }
 }
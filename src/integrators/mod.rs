@@ -1,9 +1,29 @@
 use crate::color::Color;
+use crate::geometry::Hit;
+use crate::materials::Material;
+use crate::math::{Frame, Vec3};
 use crate::ray::Ray;
+use crate::sampling::Sampler;
 use crate::world::World;
 
 pub mod ray_trace;
+pub mod path_trace;
 
-pub trait Integrator {
-    fn li(&self, ray: &Ray) -> Color;
-}
\ No newline at end of file
+pub trait Integrator: Sync {
+    fn li(&self, ray: &Ray, sampler: &mut Sampler) -> Color;
+}
+
+// light arriving at the hit straight from the light sources and leaving towards wo, one sample per light
+pub(crate) fn direct_lighting(world: &World, hit: &Hit, material: &dyn Material, frame: &Frame, wo: Vec3, sampler: &mut Sampler) -> Color {
+    let mut color = Color::BLACK;
+    for light in world.lights.iter() {
+        let Some(sample) = light.sample(hit, sampler.next_2d()) else {
+            continue;
+        };
+        let f = material.eval(wo, frame.to_local(sample.direction));
+        if f.max_component() > 0. && sample.is_visible(hit, world) {
+            color += f * sample.radiance / sample.pdf;
+        }
+    }
+    color
+}
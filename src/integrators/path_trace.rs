@@ -0,0 +1,174 @@
+use crate::color::Color;
+use crate::integrators::{direct_lighting, Integrator};
+use crate::math::Frame;
+use crate::ray::Ray;
+use crate::sampling::Sampler;
+use crate::world::World;
+
+pub struct PathTraceIntegrator<'a> {
+    pub world: &'a World,
+    // number of surface interactions along a path, including the first hit
+    pub max_depth: u32,
+    // russian roulette only starts after this many bounces
+    pub rr_depth: u32,
+}
+
+impl<'a> PathTraceIntegrator<'a> {
+    pub fn new(world: &'a World) -> Self {
+        PathTraceIntegrator {
+            world,
+            max_depth: 8,
+            rr_depth: 3,
+        }
+    }
+}
+
+impl Integrator for PathTraceIntegrator<'_> {
+
+    fn li(&self, ray: &Ray, sampler: &mut Sampler) -> Color {
+        let mut color = Color::BLACK;
+        // product of all sample weights along the path so far
+        let mut throughput = Color::WHITE;
+        let mut ray = *ray;
+        // emitters and the background seen after a diffuse or glossy bounce were already sampled by next event estimation
+        let mut count_emission = true;
+        for depth in 0..self.max_depth {
+            let Some(hit) = self.world.geometry.intersect(&ray) else {
+                if count_emission {
+                    color += throughput * self.world.background(ray.direction);
+                }
+                break;
+            };
+            let material = self.world.material(hit.material);
+            let frame = Frame::new(hit.normal);
+            let wo = frame.to_local(-ray.direction);
+            if count_emission && hit.front_face {
+                color += throughput * material.emission();
+            }
+
+            // next event estimation: point lights can only be reached by sampling them directly, and area lights are
+            // found much more often that way
+            if !material.is_specular() {
+                color += throughput * direct_lighting(self.world, &hit, material, &frame, wo, sampler);
+            }
+
+            // continue the path in a direction importance sampled by the material
+            let Some((wi, weight, _)) = material.sample(wo, &hit, sampler.next_f32(), sampler.next_2d()) else {
+                break;
+            };
+            throughput *= weight;
+            count_emission = material.is_specular();
+
+            if depth + 1 >= self.rr_depth {
+                let survival = throughput.max_component().min(0.95);
+                if sampler.next_f32() >= survival {
+                    break;
+                }
+                throughput /= survival;
+            }
+            ray = hit.spawn_ray(frame.to_world(wi));
+        }
+        color
+    }
+}
+
+#[cfg(test)]
+mod path_trace_tests {
+    use crate::color::Color;
+    use crate::geometry::{Sphere, Triangle};
+    use crate::groups::GroupContent;
+    use crate::groups::bvh_group::BvhGroup;
+    use crate::integrators::Integrator;
+    use crate::integrators::path_trace::PathTraceIntegrator;
+    use crate::integrators::ray_trace::RayTraceIntegrator;
+    use std::sync::Arc;
+    use crate::lights::environment::EnvironmentLight;
+    use crate::lights::point::PointLight;
+    use crate::materials::emissive::Emissive;
+    use crate::materials::lambertian::Lambertian;
+    use crate::math::Vec3;
+    use crate::ray::Ray;
+    use crate::sampling::Sampler;
+    use crate::world::World;
+
+    fn scene() -> World {
+        let mut world = World::new(Box::new(BvhGroup::new()));
+        world.geometry.push(GroupContent::new(Box::new(Sphere::new(Vec3::new(0., 1., 0.), 1.)), None));
+        world.geometry.push(GroupContent::new(Box::new(Triangle::new(Vec3::new(-10., 0., -10.), Vec3::new(10., 0., -10.), Vec3::new(0., 0., 10.))), None));
+        world.geometry.build();
+        world.lights.push(Box::new(PointLight::new(Vec3::new(0., 5., -3.), Color::new(30., 30., 30.))));
+        world
+    }
+
+    #[test]
+    fn single_bounce_is_direct_lighting() {
+        let world = scene();
+        let direct = RayTraceIntegrator { world: &world };
+        let mut path = PathTraceIntegrator::new(&world);
+        path.max_depth = 1;
+        let mut sampler = Sampler::new(0, 0);
+        for target in [Vec3::new(0., 1., 0.), Vec3::new(3., 0., 0.), Vec3::new(0., 0.5, 2.)] {
+            let ray = Ray::new(Vec3::new(0., 2., -6.), target - Vec3::new(0., 2., -6.), None, None);
+            assert_eq!(direct.li(&ray, &mut sampler), path.li(&ray, &mut sampler));
+        }
+    }
+
+    #[test]
+    fn indirect_light_adds_energy() {
+        let world = scene();
+        let direct = RayTraceIntegrator { world: &world };
+        let path = PathTraceIntegrator::new(&world);
+        let mut sampler = Sampler::new(0, 0);
+        // the underside of the sphere faces away from the light, but sees the lit floor
+        let ray = Ray::new(Vec3::new(0., 0.2, -5.), Vec3::Z, None, None);
+        assert_eq!(direct.li(&ray, &mut sampler), Color::BLACK);
+        let mut sum = Color::BLACK;
+        for _ in 0..256 {
+            sum += path.li(&ray, &mut sampler);
+        }
+        assert!(sum.r > 0.);
+    }
+
+    #[test]
+    fn emitters_are_counted_once() {
+        let mut world = World::new(Box::new(BvhGroup::new()));
+        let emissive = world.add_material(Arc::new(Emissive::new(Color::WHITE)));
+        world.add_geometry(Box::new(Sphere::new(Vec3::new(0., 4., 0.), 1.)), Some(emissive));
+        world.add_geometry(Box::new(Triangle::new(Vec3::new(-10., 0., -10.), Vec3::new(10., 0., -10.), Vec3::new(0., 0., 10.))), None);
+        world.geometry.build();
+        assert_eq!(world.lights.len(), 1);
+        let mut path = PathTraceIntegrator::new(&world);
+        let mut sampler = Sampler::new(0, 0);
+        // seen directly
+        assert_eq!(path.li(&Ray::new(Vec3::new(0., 4., -5.), Vec3::Z, None, None), &mut sampler), Color::WHITE);
+        // the floor below receives E = π L r² / d², bounce rays hitting the sphere must not add to it
+        path.max_depth = 2;
+        let ray = Ray::new(Vec3::new(0., 1., -1.), Vec3::new(0., -1., 1.), None, None);
+        let n = 10000;
+        let mut sum = Color::BLACK;
+        for _ in 0..n {
+            sum += path.li(&ray, &mut sampler);
+        }
+        let expected = 1. / 16.;
+        assert!((sum.r / n as f32 - expected).abs() < 0.01 * expected, "{}", sum.r / n as f32);
+    }
+
+    #[test]
+    fn white_furnace() {
+        // a convex diffuse object in a uniform environment reflects its albedo times the environment
+        let mut world = World::new(Box::new(BvhGroup::new()));
+        let gray = world.add_material(Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))));
+        world.add_geometry(Box::new(Sphere::new(Vec3::ZERO, 1.)), Some(gray));
+        world.geometry.build();
+        world.lights.push(Box::new(EnvironmentLight::uniform(Color::WHITE)));
+        let path = PathTraceIntegrator::new(&world);
+        let mut sampler = Sampler::new(0, 0);
+        assert_eq!(path.li(&Ray::new(Vec3::new(0., 0., -5.), Vec3::Y, None, None), &mut sampler), Color::WHITE);
+        let n = 10000;
+        let mut sum = Color::BLACK;
+        for _ in 0..n {
+            sum += path.li(&Ray::new(Vec3::new(0., 0.5, -5.), Vec3::Z, None, None), &mut sampler);
+        }
+        assert!((sum.g / n as f32 - 0.5).abs() < 0.01, "{:?}", sum / n as f32);
+    }
+}
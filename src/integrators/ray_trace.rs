@@ -1,8 +1,8 @@
-use std::f32::consts::PI;
 use crate::color::Color;
-use crate::integrators::Integrator;
-use crate::math::Vector;
+use crate::integrators::{direct_lighting, Integrator};
+use crate::math::Frame;
 use crate::ray::Ray;
+use crate::sampling::Sampler;
 use crate::world::World;
 
 
@@ -12,13 +12,18 @@ pub struct RayTraceIntegrator<'a> {
 
 impl Integrator for RayTraceIntegrator<'_> {
 
-    fn li(&self, ray: &Ray) -> Color {
+    fn li(&self, ray: &Ray, sampler: &mut Sampler) -> Color {
         let mut color = Color::BLACK;
         if let Some(hit) = self.world.geometry.intersect(ray){
-            for light in self.world.lights.iter() {
-                let (c, dir) = light.sample(hit.point, self.world);
-                color += c * hit.normal.dot(&dir) / PI;
+            let material = self.world.material(hit.material);
+            let frame = Frame::new(hit.normal);
+            let wo = frame.to_local(-ray.direction);
+            if hit.front_face {
+                color += material.emission();
             }
+            color += direct_lighting(self.world, &hit, material, &frame, wo, sampler);
+        } else {
+            color = self.world.background(ray.direction);
         }
         color
     }
@@ -0,0 +1,94 @@
+use std::f32::consts::PI;
+use crate::color::Color;
+use crate::geometry::Hit;
+use crate::lights::{LightSample, LightSource};
+use crate::math::{Frame, Vec2, Vec3, Vector};
+use crate::sampling::warp::{uniform_cone, uniform_cone_pdf};
+
+// light from an infinitely distant source, like the sun. With an angular diameter the source is a disk in the sky,
+// which casts soft shadows, without one all light arrives from exactly the same direction
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct DirectionalLight {
+    // normalized direction the light travels in
+    pub direction: Vec3,
+    // irradiance on a surface facing the light
+    pub irradiance: Color,
+    // cosine of the angular radius, 1 for a single direction
+    cos_max: f32,
+}
+
+impl DirectionalLight {
+    // angular_diameter in radians up to pi, 0 for hard shadows
+    pub fn new(direction: Vec3, irradiance: Color, angular_diameter: f32) -> Self {
+        assert!((0. ..=PI).contains(&angular_diameter), "the angular diameter has to be in [0, pi]");
+        DirectionalLight {
+            direction: direction.normalized(),
+            irradiance,
+            cos_max: (angular_diameter / 2.).cos(),
+        }
+    }
+
+    // radiance of the disk, chosen so that it delivers the given irradiance
+    fn radiance(&self) -> Color {
+        self.irradiance / (PI * (1. - self.cos_max * self.cos_max))
+    }
+}
+
+impl LightSource for DirectionalLight {
+    fn sample(&self, _hit: &Hit, u: Vec2) -> Option<LightSample> {
+        if self.cos_max >= 1. {
+            return Some(LightSample {
+                radiance: self.irradiance,
+                direction: -self.direction,
+                distance: f32::INFINITY,
+                pdf: 1.,
+            });
+        }
+        Some(LightSample {
+            radiance: self.radiance(),
+            direction: Frame::new(-self.direction).to_world(uniform_cone(u, self.cos_max)),
+            distance: f32::INFINITY,
+            pdf: uniform_cone_pdf(self.cos_max),
+        })
+    }
+
+    // only a disk can be seen, a single direction is never hit
+    fn background(&self, direction: Vec3) -> Color {
+        if self.cos_max < 1. && -direction.dot(&self.direction) >= self.cos_max {
+            self.radiance()
+        } else {
+            Color::BLACK
+        }
+    }
+}
+
+#[cfg(test)]
+mod directional_tests {
+    use crate::color::Color;
+    use crate::geometry::Hit;
+    use crate::lights::{irradiance, LightSource};
+    use crate::lights::directional::DirectionalLight;
+    use crate::math::{Vec2, Vec3, Vector};
+    use crate::sampling::Sampler;
+
+    #[test]
+    fn hard_and_soft() {
+        let hard = DirectionalLight::new(Vec3::new(0., -2., 0.), Color::WHITE, 0.);
+        let s = hard.sample(&Hit::default(), Vec2::ZERO).unwrap();
+        assert_eq!(s.direction, Vec3::Y);
+        assert_eq!(s.radiance, Color::WHITE);
+        assert_eq!(hard.background(Vec3::Y), Color::BLACK);
+        // a large disk still delivers the same irradiance
+        let soft = DirectionalLight::new(-Vec3::Y, Color::WHITE, 20f32.to_radians());
+        let hit = Hit { normal: Vec3::Y, ..Hit::default() };
+        let mut sampler = Sampler::new(0, 0);
+        for _ in 0..100 {
+            let s = soft.sample(&hit, sampler.next_2d()).unwrap();
+            assert!(s.direction.y >= 10f32.to_radians().cos() - 1e-5);
+        }
+        let e = irradiance(&soft, &hit, &mut sampler, 10000);
+        assert!((e - 1.).abs() < 0.001, "{}", e);
+        assert!(soft.background(Vec3::Y).r > 1.);
+        assert_eq!(soft.background(Vec3::new(0., 1., 1.).normalized()), Color::BLACK);
+    }
+}
@@ -0,0 +1,59 @@
+use std::f32::consts::PI;
+use crate::color::Color;
+use crate::geometry::Hit;
+use crate::lights::{area_sample, LightSample, LightSource};
+use crate::math::{Frame, Vec2, Vec3, Vector};
+use crate::sampling::warp::concentric_disk;
+
+// emits to the side the normal points to
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct DiskLight {
+    center: Vec3,
+    frame: Frame,
+    radius: f32,
+    radiance: Color,
+}
+
+impl DiskLight {
+    pub fn new(center: Vec3, normal: Vec3, radius: f32, radiance: Color) -> Self {
+        DiskLight {
+            center,
+            frame: Frame::new(normal.normalized()),
+            radius,
+            radiance,
+        }
+    }
+
+    pub fn area(&self) -> f32 {
+        PI * self.radius * self.radius
+    }
+}
+
+impl LightSource for DiskLight {
+    fn sample(&self, hit: &Hit, u: Vec2) -> Option<LightSample> {
+        let d = concentric_disk(u) * self.radius;
+        let point = self.center + self.frame.to_world(Vec3::new(d.x, d.y, 0.));
+        area_sample(hit, point, self.frame.n, 1. / self.area(), self.radiance)
+    }
+}
+
+#[cfg(test)]
+mod disk_tests {
+    use std::f32::consts::PI;
+    use crate::color::Color;
+    use crate::geometry::Hit;
+    use crate::lights::irradiance;
+    use crate::lights::disk::DiskLight;
+    use crate::math::Vec3;
+    use crate::sampling::Sampler;
+
+    #[test]
+    fn on_axis() {
+        // a disk of radius r at distance h on the axis gives E = π L r² / (h² + r²)
+        let light = DiskLight::new(Vec3::new(0., 0., 2.), -Vec3::Z, 1., Color::WHITE);
+        let hit = Hit { normal: Vec3::Z, geometric_normal: Vec3::Z, ..Hit::default() };
+        let e = irradiance(&light, &hit, &mut Sampler::new(0, 0), 20000);
+        let expected = PI / 5.;
+        assert!((e - expected).abs() < 0.01 * expected, "{}", e);
+    }
+}
@@ -0,0 +1,144 @@
+use std::f32::consts::PI;
+use crate::color::Color;
+use crate::geometry::Hit;
+use crate::lights::{LightSample, LightSource};
+use crate::math::{Transform, Vec2, Vec3};
+use crate::sampling::distribution::Distribution2D;
+
+// light arriving from infinitely far away in every direction, given by an equirectangular image. The top row is +y,
+// the left edge +x and the image continues towards +z
+pub struct EnvironmentLight {
+    width: usize,
+    height: usize,
+    pixels: Vec<Color>,
+    // map to world
+    transform: Transform,
+    // brightness of the directions, weighted by the solid angle their pixel covers
+    distribution: Distribution2D,
+}
+
+impl EnvironmentLight {
+    // pixels are stored row by row. transform has to be a rotation
+    pub fn new(width: usize, height: usize, pixels: Vec<Color>, transform: Transform, intensity: f32) -> Self {
+        assert_eq!(pixels.len(), width * height, "the environment map has to have width * height pixels");
+        let pixels: Vec<Color> = pixels.into_iter().map(|c| c * intensity).collect();
+        // pixels towards the poles cover less of the sphere
+        let weights: Vec<f32> = pixels.iter().enumerate().map(|(i, c)| {
+            let theta = ((i / width) as f32 + 0.5) / height as f32 * PI;
+            c.luminance().max(0.) * theta.sin()
+        }).collect();
+        EnvironmentLight {
+            width,
+            height,
+            pixels,
+            transform,
+            distribution: Distribution2D::new(&weights, width),
+        }
+    }
+
+    // a single color in every direction
+    pub fn uniform(color: Color) -> Self {
+        Self::new(1, 1, vec![color], Transform::IDENTITY, 1.)
+    }
+
+    // the direction of a point on the map and sin θ, the factor by which the map is squeezed there
+    pub(crate) fn to_direction(uv: Vec2) -> (Vec3, f32) {
+        let (phi, theta) = (uv.x * 2. * PI, uv.y * PI);
+        let sin_theta = theta.sin();
+        (Vec3::new(sin_theta * phi.cos(), theta.cos(), sin_theta * phi.sin()), sin_theta)
+    }
+
+    pub(crate) fn to_uv(direction: Vec3) -> Vec2 {
+        let phi = direction.z.atan2(direction.x);
+        let phi = if phi < 0. { phi + 2. * PI } else { phi };
+        Vec2::new(phi / (2. * PI), direction.y.clamp(-1., 1.).acos() / PI)
+    }
+
+    // the pixels are constant over their area, so the density of the distribution matches the radiance exactly
+    fn lookup(&self, uv: Vec2) -> Color {
+        let x = ((uv.x * self.width as f32) as usize).min(self.width - 1);
+        let y = ((uv.y * self.height as f32) as usize).min(self.height - 1);
+        self.pixels[y * self.width + x]
+    }
+
+    // density per solid angle with which sample() picks the world space direction
+    pub fn pdf(&self, direction: Vec3) -> f32 {
+        let local = self.transform.inverse().vector(direction);
+        let uv = Self::to_uv(local);
+        let sin_theta = (uv.y * PI).sin();
+        if sin_theta <= 0. {
+            return 0.;
+        }
+        self.distribution.pdf(uv) / (2. * PI * PI * sin_theta)
+    }
+}
+
+impl LightSource for EnvironmentLight {
+    fn sample(&self, _hit: &Hit, u: Vec2) -> Option<LightSample> {
+        let (uv, pdf) = self.distribution.sample(u);
+        let (local, sin_theta) = Self::to_direction(uv);
+        if pdf <= 0. || sin_theta <= 0. {
+            return None;
+        }
+        Some(LightSample {
+            radiance: self.lookup(uv),
+            direction: self.transform.vector(local),
+            distance: f32::INFINITY,
+            // the map covers 2π x π, stretched by sin θ on the sphere
+            pdf: pdf / (2. * PI * PI * sin_theta),
+        })
+    }
+
+    fn background(&self, direction: Vec3) -> Color {
+        self.lookup(Self::to_uv(self.transform.inverse().vector(direction)))
+    }
+}
+
+#[cfg(test)]
+mod environment_tests {
+    use std::f32::consts::PI;
+    use crate::color::Color;
+    use crate::geometry::Hit;
+    use crate::lights::{irradiance, LightSource};
+    use crate::lights::environment::EnvironmentLight;
+    use crate::math::{Transform, Vec3, Vector};
+    use crate::sampling::Sampler;
+
+    #[test]
+    fn uniform() {
+        // a constant environment gives E = π L on any surface
+        let light = EnvironmentLight::uniform(Color::WHITE);
+        let hit = Hit { normal: Vec3::new(1., 2., 0.5).normalized(), ..Hit::default() };
+        let e = irradiance(&light, &hit, &mut Sampler::new(0, 0), 20000);
+        assert!((e - PI).abs() < 0.02 * PI, "{}", e);
+        assert_eq!(light.background(Vec3::Y), Color::WHITE);
+    }
+
+    #[test]
+    fn bright_spot() {
+        // a black map with a single bright pixel
+        let (width, height) = (64, 32);
+        let mut pixels = vec![Color::BLACK; width * height];
+        pixels[8 * width + 16] = Color::new(1000., 1000., 1000.);
+        let light = EnvironmentLight::new(width, height, pixels, Transform::IDENTITY, 1.);
+        let mut sampler = Sampler::new(1, 0);
+        let hit = Hit::default();
+        for _ in 0..100 {
+            // every sample ends up in the bright pixel, with the density sample() claims
+            let s = light.sample(&hit, sampler.next_2d()).unwrap();
+            assert_eq!(s.radiance.r, 1000.);
+            assert!((s.pdf / light.pdf(s.direction) - 1.).abs() < 1e-3);
+            assert_eq!(light.background(s.direction).r, 1000.);
+        }
+        // the irradiance matches integrating over the pixel: L * solid angle * cos
+        let s = light.sample(&hit, sampler.next_2d()).unwrap();
+        let hit = Hit { normal: s.direction, ..Hit::default() };
+        let solid_angle = (2. * PI / 64.) * ((8. / 32. * PI).cos() - (9. / 32. * PI).cos());
+        let e = irradiance(&light, &hit, &mut Sampler::new(0, 0), 10000);
+        assert!((e - 1000. * solid_angle).abs() < 0.02 * 1000. * solid_angle, "{} {}", e, 1000. * solid_angle);
+        // intensity and rotation
+        let rotated = EnvironmentLight::new(1, 2, vec![Color::WHITE, Color::BLACK], Transform::rotate_x(PI), 2.);
+        assert_eq!(rotated.background(-Vec3::Y), Color::new(2., 2., 2.));
+        assert_eq!(rotated.background(Vec3::Y), Color::BLACK);
+    }
+}
@@ -0,0 +1,83 @@
+use crate::color::Color;
+use crate::geometry::Hit;
+use crate::lights::{area_sample, LightSample, LightSource};
+use crate::lights::triangle::TriangleLight;
+use crate::math::{Vec2, Vec3};
+
+// any number of emitting triangles sampled as a single light. Triangles are picked proportional to their area, so
+// every point of the surface is equally likely and large meshes don't cost more per sample than small ones
+pub struct MeshLight {
+    triangles: Vec<TriangleLight>,
+    // running sum of the triangle areas
+    cdf: Vec<f32>,
+}
+
+impl MeshLight {
+    // triangles use the winding of Triangle. None if they don't cover any area
+    pub fn new(triangles: impl IntoIterator<Item = [Vec3; 3]>, radiance: Color) -> Option<Self> {
+        let triangles: Vec<TriangleLight> = triangles.into_iter()
+            .map(|[v0, v1, v2]| TriangleLight::new(v0, v1, v2, radiance))
+            .filter(|t| t.area() > 0.)
+            .collect();
+        if triangles.is_empty() {
+            return None;
+        }
+        let cdf = triangles.iter()
+            .scan(0., |sum, t| {
+                *sum += t.area();
+                Some(*sum)
+            })
+            .collect();
+        Some(MeshLight {
+            triangles,
+            cdf,
+        })
+    }
+
+    pub fn area(&self) -> f32 {
+        *self.cdf.last().unwrap()
+    }
+}
+
+impl LightSource for MeshLight {
+    fn sample(&self, hit: &Hit, u: Vec2) -> Option<LightSample> {
+        let area = self.area();
+        let target = u.x * area;
+        let index = self.cdf.partition_point(|&sum| sum <= target).min(self.triangles.len() - 1);
+        // reuse u.x for the point within the triangle
+        let start = if index == 0 { 0. } else { self.cdf[index - 1] };
+        let triangle = &self.triangles[index];
+        let ux = ((target - start) / triangle.area()).clamp(0., 1.);
+        let point = triangle.sample_point(Vec2::new(ux, u.y));
+        area_sample(hit, point, triangle.normal(), 1. / area, triangle.radiance())
+    }
+}
+
+#[cfg(test)]
+mod mesh_tests {
+    use crate::color::Color;
+    use crate::geometry::Hit;
+    use crate::lights::irradiance;
+    use crate::lights::mesh::MeshLight;
+    use crate::lights::rectangle::RectangleLight;
+    use crate::math::Vec3;
+    use crate::sampling::Sampler;
+
+    #[test]
+    fn matches_rectangle() {
+        // a large and a small triangle covering a rectangle, which differ a lot in area
+        let (a, b, c, d) = (Vec3::new(-2., 1., -1.), Vec3::new(-2., 1., 1.), Vec3::new(1.5, 1., 1.), Vec3::new(2., 1., -1.));
+        let mesh = MeshLight::new([[a, b, d], [b, c, d]], Color::WHITE).unwrap();
+        assert_eq!(mesh.area(), 7.5);
+        let hit = Hit { point: Vec3::new(0.3, 0., 0.2), normal: Vec3::Y, geometric_normal: Vec3::Y, ..Hit::default() };
+        let mut sampler = Sampler::new(0, 0);
+        let n = 40000;
+        let actual = irradiance(&mesh, &hit, &mut sampler, n);
+        // the same trapezoid, split into a rectangle and a triangle
+        let rectangle = RectangleLight::new(a, Vec3::new(3.5, 0., 0.), b - a, Color::WHITE);
+        let corner = MeshLight::new([[Vec3::new(1.5, 1., -1.), c, d]], Color::WHITE).unwrap();
+        let expected = irradiance(&rectangle, &hit, &mut sampler, n) + irradiance(&corner, &hit, &mut sampler, n);
+        assert!((actual - expected).abs() < 0.01 * expected, "{} {}", actual, expected);
+        assert!(MeshLight::new([[a, a, b]], Color::WHITE).is_none());
+    }
+}
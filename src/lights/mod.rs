@@ -1,9 +1,85 @@
 use crate::color::Color;
-use crate::math::Vec3;
+use crate::geometry::Hit;
+use crate::math::{EPSILON, Vec2, Vec3, Vector};
+use crate::ray::Ray;
 use crate::world::World;
 
 pub mod point;
+pub mod spot;
+pub mod directional;
+pub mod rectangle;
+pub mod disk;
+pub mod sphere;
+pub mod triangle;
+pub mod mesh;
+pub mod transformed;
+pub mod environment;
+pub mod sky;
 
-pub trait LightSource {
-    fn sample(&self, point: Vec3, world: &World) -> (Color, Vec3);
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct LightSample {
+    // radiance arriving at the hit, if nothing is in the way. Point lights return intensity / distance²
+    pub radiance: Color,
+    // normalized direction from the hit towards the light
+    pub direction: Vec3,
+    pub distance: f32,
+    // density of the direction with respect to solid angle. 1 for lights that can only be reached in one direction
+    pub pdf: f32,
+}
+
+impl LightSample {
+    // ends just before the sampled point, so the emitter itself doesn't block it
+    pub fn shadow_ray(&self, hit: &Hit) -> Ray {
+        let origin = hit.spawn_point(self.direction);
+        if self.distance.is_infinite() {
+            return Ray::new(origin, self.direction, None, None);
+        }
+        let to_light = hit.point + self.direction * self.distance - origin;
+        Ray::new(origin, to_light, None, Some(to_light.length() - EPSILON))
+    }
+
+    pub fn is_visible(&self, hit: &Hit, world: &World) -> bool {
+        !world.geometry.does_intersect(&self.shadow_ray(hit))
+    }
+}
+
+pub trait LightSource: Send + Sync {
+    // picks a direction towards the light as seen from the hit. Occlusion is not tested. None if the light can't
+    // illuminate the hit at all
+    fn sample(&self, hit: &Hit, u: Vec2) -> Option<LightSample>;
+    // radiance arriving along a ray that leaves the scene in the given direction. Only lights at infinity have any
+    fn background(&self, _direction: Vec3) -> Color {
+        Color::BLACK
+    }
+}
+
+// turns a point picked on an emitting surface with a density per area into a sample with a density per solid angle.
+// The surface only emits to the side its normal points to
+pub(crate) fn area_sample(hit: &Hit, point: Vec3, normal: Vec3, area_pdf: f32, radiance: Color) -> Option<LightSample> {
+    let to_light = point - hit.point;
+    let distance_squared = to_light.length_squared();
+    if distance_squared == 0. {
+        return None;
+    }
+    let distance = distance_squared.sqrt();
+    let direction = to_light / distance;
+    let cos_light = -direction.dot(&normal);
+    if cos_light <= 0. {
+        return None;
+    }
+    Some(LightSample {
+        radiance,
+        direction,
+        distance,
+        pdf: area_pdf * distance_squared / cos_light,
+    })
+}
+
+// Monte Carlo estimate of the irradiance at hit, in luminance, for the tests comparing lights with each other or with
+// a closed form
+#[cfg(test)]
+pub(crate) fn irradiance(light: &dyn LightSource, hit: &Hit, sampler: &mut crate::sampling::Sampler, n: usize) -> f32 {
+    (0..n).filter_map(|_| light.sample(hit, sampler.next_2d()))
+        .map(|s| s.radiance.luminance() * s.direction.dot(&hit.normal).max(0.) / s.pdf)
+        .sum::<f32>() / n as f32
 }
@@ -1,8 +1,7 @@
 use crate::color::Color;
-use crate::lights::LightSource;
-use crate::math::{Vec3, Vector};
-use crate::ray::Ray;
-use crate::world::World;
+use crate::geometry::Hit;
+use crate::lights::{LightSample, LightSource};
+use crate::math::{Vec2, Vec3, Vector};
 
 #[derive(Copy, Clone, Debug, Default, PartialEq)]
 pub struct PointLight {
@@ -19,14 +18,16 @@ impl PointLight {
 }
 
 impl LightSource for PointLight {
-    fn sample(&self, point: Vec3, world: &World) -> (Color, Vec3) {
-        let direction = self.center - point;
-        // TODO: Optimize usage of length
-        let hit = world.geometry.does_intersect(&Ray::new(point, direction, None, Some(direction.length())));
-        if !hit {
-            (self.intensity / direction.length_squared(), direction.normalized())
-        } else {
-            (Color::new(0., 0., 0.), direction.normalized())
-        }
+    fn sample(&self, hit: &Hit, _u: Vec2) -> Option<LightSample> {
+        let to_light = self.center - hit.point;
+        let distance_squared = to_light.length_squared();
+        let distance = distance_squared.sqrt();
+        // there is only one direction towards the light
+        Some(LightSample {
+            radiance: self.intensity / distance_squared,
+            direction: to_light / distance,
+            distance,
+            pdf: 1.,
+        })
     }
 }
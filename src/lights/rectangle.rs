@@ -0,0 +1,68 @@
+use crate::color::Color;
+use crate::geometry::Hit;
+use crate::lights::{area_sample, LightSample, LightSource};
+use crate::math::{Vec2, Vec3, Vector};
+
+// parallelogram spanned by two edges from a corner. It emits to the side of edge_u x edge_v
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct RectangleLight {
+    corner: Vec3,
+    edge_u: Vec3,
+    edge_v: Vec3,
+    normal: Vec3,
+    area: f32,
+    radiance: Color,
+}
+
+impl RectangleLight {
+    pub fn new(corner: Vec3, edge_u: Vec3, edge_v: Vec3, radiance: Color) -> Self {
+        let cross = edge_u.cross(&edge_v);
+        RectangleLight {
+            corner,
+            edge_u,
+            edge_v,
+            normal: cross.normalized(),
+            area: cross.length(),
+            radiance,
+        }
+    }
+
+    pub fn normal(&self) -> Vec3 {
+        self.normal
+    }
+
+    pub fn area(&self) -> f32 {
+        self.area
+    }
+}
+
+impl LightSource for RectangleLight {
+    fn sample(&self, hit: &Hit, u: Vec2) -> Option<LightSample> {
+        let point = self.corner + self.edge_u * u.x + self.edge_v * u.y;
+        area_sample(hit, point, self.normal, 1. / self.area, self.radiance)
+    }
+}
+
+#[cfg(test)]
+mod rectangle_tests {
+    use crate::color::Color;
+    use crate::geometry::Hit;
+    use crate::lights::{irradiance, LightSource};
+    use crate::lights::rectangle::RectangleLight;
+    use crate::math::Vec3;
+    use crate::sampling::Sampler;
+
+    #[test]
+    fn small_square() {
+        // a small square right above the point, E = L * A * cos / d²
+        let light = RectangleLight::new(Vec3::new(0.05, 1., -0.05), Vec3::new(0., 0., 0.1), Vec3::new(-0.1, 0., 0.), Color::WHITE);
+        assert_eq!(light.normal(), Vec3::new(0., -1., 0.));
+        let hit = Hit { normal: Vec3::Y, geometric_normal: Vec3::Y, ..Hit::default() };
+        let mut sampler = Sampler::new(0, 0);
+        let e = irradiance(&light, &hit, &mut sampler, 10000);
+        assert!((e - 0.01).abs() < 1e-4, "{}", e);
+        // the back side doesn't emit
+        let above = Hit { point: Vec3::new(0., 2., 0.), ..hit };
+        assert!(light.sample(&above, sampler.next_2d()).is_none());
+    }
+}
@@ -0,0 +1,232 @@
+use std::f32::consts::PI;
+use crate::color::Color;
+use crate::geometry::Hit;
+use crate::lights::{LightSample, LightSource};
+use crate::lights::environment::EnvironmentLight;
+use crate::math::{Frame, Vec2, Vec3, Vector};
+use crate::sampling::distribution::Distribution2D;
+use crate::sampling::warp::{uniform_cone, uniform_cone_pdf};
+
+// resolution of the table the sky is importance sampled with
+const TABLE_WIDTH: usize = 128;
+const TABLE_HEIGHT: usize = 64;
+// luminance of the sun outside the atmosphere in kcd/m²
+const SUN_LUMINANCE: f32 = 1.6e6;
+
+// clear sky after Preetham et al. 1999, "A Practical Analytic Model for Daylight", with the sun as a disk. Radiance
+// is in kcd/m² times the intensity. Below the horizon it is black, the ground has to be part of the scene
+pub struct SkyLight {
+    sun_direction: Vec3,
+    // cosine of the angular radius of the sun, 1 without a sun
+    sun_cos_max: f32,
+    sun_radiance: Color,
+    // zenith luminance and chromaticity, divided by the Perez function at the zenith
+    zenith: [f32; 3],
+    perez: [[f32; 5]; 3],
+    intensity: f32,
+    // luminance of the sky over the equirectangular map used by EnvironmentLight
+    distribution: Distribution2D,
+    // probability of sampling the sun instead of the sky
+    sun_probability: f32,
+}
+
+// Perez et al. 1993: relative luminance at angle theta from the zenith and gamma from the sun
+fn perez(c: &[f32; 5], cos_theta: f32, gamma: f32) -> f32 {
+    (1. + c[0] * (c[1] / cos_theta).exp()) * (1. + c[2] * (c[3] * gamma).exp() + c[4] * gamma.cos().powi(2))
+}
+
+fn xyy_to_rgb(x: f32, y: f32, luminance: f32) -> Color {
+    if y <= 0. {
+        return Color::BLACK;
+    }
+    let (cx, cy, cz) = (x * luminance / y, luminance, (1. - x - y) * luminance / y);
+    Color::new(
+        (3.2406 * cx - 1.5372 * cy - 0.4986 * cz).max(0.),
+        (-0.9689 * cx + 1.8758 * cy + 0.0415 * cz).max(0.),
+        (0.0557 * cx - 0.2040 * cy + 1.0570 * cz).max(0.),
+    )
+}
+
+impl SkyLight {
+    // angles in degrees. The azimuth is measured from +z towards +x. Turbidity ranges from 2 (clear) to about 10
+    // (hazy), the model breaks down outside of that. sun_size is the angular diameter of the sun, 0 removes it
+    pub fn new(elevation: f32, azimuth: f32, turbidity: f32, intensity: f32, sun_size: f32) -> Self {
+        let (elevation, azimuth) = (elevation.to_radians(), azimuth.to_radians());
+        let sun_direction = Vec3::new(elevation.cos() * azimuth.sin(), elevation.sin(), elevation.cos() * azimuth.cos());
+        let t = turbidity;
+        // angle between the sun and the zenith
+        let theta_s = PI / 2. - elevation.clamp(0., PI / 2.);
+        let coefficients = [
+            [0.1787 * t - 1.4630, -0.3554 * t + 0.4275, -0.0227 * t + 5.3251, 0.1206 * t - 2.5771, -0.0670 * t + 0.3703],
+            [-0.0193 * t - 0.2592, -0.0665 * t + 0.0008, -0.0004 * t + 0.2125, -0.0641 * t - 0.8989, -0.0033 * t + 0.0452],
+            [-0.0167 * t - 0.2608, -0.0950 * t + 0.0092, -0.0079 * t + 0.2102, -0.0441 * t - 1.6537, -0.0109 * t + 0.0529],
+        ];
+        let chi = (4. / 9. - t / 120.) * (PI - 2. * theta_s);
+        let luminance = ((4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192).max(0.);
+        let (s1, s2, s3) = (theta_s, theta_s * theta_s, theta_s * theta_s * theta_s);
+        let x = t * t * (0.00166 * s3 - 0.00375 * s2 + 0.00209 * s1)
+            + t * (-0.02903 * s3 + 0.06377 * s2 - 0.03202 * s1 + 0.00394)
+            + (0.11693 * s3 - 0.21196 * s2 + 0.06052 * s1 + 0.25886);
+        let y = t * t * (0.00275 * s3 - 0.00610 * s2 + 0.00317 * s1)
+            + t * (-0.04214 * s3 + 0.08970 * s2 - 0.04153 * s1 + 0.00516)
+            + (0.15346 * s3 - 0.26756 * s2 + 0.06670 * s1 + 0.26688);
+        let zenith = [luminance, x, y];
+        let zenith = [0, 1, 2].map(|i| zenith[i] / perez(&coefficients[i], 1., theta_s));
+
+        let sun_radius = (sun_size / 2.).to_radians();
+        let sun_cos_max = if sun_size > 0. && elevation > -sun_radius { sun_radius.cos() } else { 1. };
+        let mut sky = SkyLight {
+            sun_direction,
+            sun_cos_max,
+            sun_radiance: Self::sun_radiance(elevation, turbidity) * intensity,
+            zenith,
+            perez: coefficients,
+            intensity,
+            distribution: Distribution2D::new(&[1.], 1),
+            sun_probability: 0.,
+        };
+
+        // the sky is sampled by a table of its luminance, the radiance itself is always evaluated exactly
+        let mut weights = vec![0.; TABLE_WIDTH * TABLE_HEIGHT];
+        let mut sky_power = 0.;
+        for (i, w) in weights.iter_mut().enumerate() {
+            let uv = Vec2::new(((i % TABLE_WIDTH) as f32 + 0.5) / TABLE_WIDTH as f32, ((i / TABLE_WIDTH) as f32 + 0.5) / TABLE_HEIGHT as f32);
+            let (direction, sin_theta) = EnvironmentLight::to_direction(uv);
+            if direction.y > 0. {
+                // never 0 above the horizon, or the parts of the pixels with some radiance would be lost
+                *w = sky.sky_radiance(direction).luminance().max(1e-6) * sin_theta;
+                sky_power += *w;
+            }
+        }
+        sky.distribution = Distribution2D::new(&weights, TABLE_WIDTH);
+        let sky_power = sky_power * 2. * PI * PI / (TABLE_WIDTH * TABLE_HEIGHT) as f32;
+        if sky.sun_cos_max < 1. {
+            let sun_power = sky.sun_radiance.luminance() * 2. * PI * (1. - sky.sun_cos_max);
+            sky.sun_probability = sun_power / (sun_power + sky_power);
+        }
+        sky
+    }
+
+    pub fn sun_direction(&self) -> Vec3 {
+        self.sun_direction
+    }
+
+    // the light of the sun is scattered out by air molecules (Rayleigh) and haze (aerosols), depending on the length
+    // of its path through the atmosphere. Preetham et al. appendix A.2, without the minor absorption terms
+    fn sun_radiance(elevation: f32, turbidity: f32) -> Color {
+        let zenith_degrees = 90. - elevation.to_degrees();
+        if zenith_degrees >= 93.8 {
+            return Color::BLACK;
+        }
+        // relative optical mass, Kasten 1966
+        let mass = 1. / ((PI / 2. - elevation).cos() + 0.15 * (93.885 - zenith_degrees).powf(-1.253));
+        let beta = 0.04608 * turbidity - 0.04586;
+        // wavelengths of the sRGB primaries in micrometers
+        let transmittance = [0.65f32, 0.57, 0.475].map(|lambda| {
+            let rayleigh = (-0.008735 * lambda.powf(-4.08) * mass).exp();
+            let aerosol = (-beta * lambda.powf(-1.3) * mass).exp();
+            rayleigh * aerosol
+        });
+        Color::new(transmittance[0], transmittance[1], transmittance[2]) * SUN_LUMINANCE
+    }
+
+    fn sky_radiance(&self, direction: Vec3) -> Color {
+        if direction.y <= 0. {
+            return Color::BLACK;
+        }
+        let gamma = direction.dot(&self.sun_direction).clamp(-1., 1.).acos();
+        let [luminance, x, y] = [0, 1, 2].map(|i| self.zenith[i] * perez(&self.perez[i], direction.y, gamma));
+        xyy_to_rgb(x, y, luminance.max(0.)) * self.intensity
+    }
+
+    fn is_in_sun(&self, direction: Vec3) -> bool {
+        self.sun_cos_max < 1. && direction.dot(&self.sun_direction) >= self.sun_cos_max
+    }
+
+    pub fn radiance(&self, direction: Vec3) -> Color {
+        let sky = self.sky_radiance(direction);
+        if self.is_in_sun(direction) { sky + self.sun_radiance } else { sky }
+    }
+}
+
+impl LightSource for SkyLight {
+    // sky and sun are sampled as two separate lights, one of them picked at random
+    fn sample(&self, _hit: &Hit, u: Vec2) -> Option<LightSample> {
+        if u.x < self.sun_probability {
+            let u = Vec2::new(u.x / self.sun_probability, u.y);
+            let direction = Frame::new(self.sun_direction).to_world(uniform_cone(u, self.sun_cos_max));
+            return Some(LightSample {
+                radiance: self.sun_radiance,
+                direction,
+                distance: f32::INFINITY,
+                pdf: self.sun_probability * uniform_cone_pdf(self.sun_cos_max),
+            });
+        }
+        let u = Vec2::new((u.x - self.sun_probability) / (1. - self.sun_probability), u.y);
+        let (uv, pdf) = self.distribution.sample(u);
+        let (direction, sin_theta) = EnvironmentLight::to_direction(uv);
+        if pdf <= 0. || sin_theta <= 0. {
+            return None;
+        }
+        Some(LightSample {
+            radiance: self.sky_radiance(direction),
+            direction,
+            distance: f32::INFINITY,
+            pdf: (1. - self.sun_probability) * pdf / (2. * PI * PI * sin_theta),
+        })
+    }
+
+    fn background(&self, direction: Vec3) -> Color {
+        self.radiance(direction)
+    }
+}
+
+#[cfg(test)]
+mod sky_tests {
+    use std::f32::consts::PI;
+    use crate::geometry::Hit;
+    use crate::lights::{irradiance, LightSource};
+    use crate::lights::sky::SkyLight;
+    use crate::math::{ApproxEq, Vec3, Vector};
+    use crate::sampling::Sampler;
+    use crate::sampling::warp::uniform_sphere;
+
+    #[test]
+    fn zenith() {
+        // turbidity 2 with the sun at 45°: Y_z = (4.0453 T - 4.9710) tan χ - 0.2155 T + 2.4192
+        let sky = SkyLight::new(45., 0., 2., 1., 0.);
+        let chi = (4. / 9. - 2. / 120.) * PI / 2.;
+        let expected = (4.0453 * 2. - 4.9710) * chi.tan() - 0.2155 * 2. + 2.4192;
+        assert!((sky.radiance(Vec3::Y).luminance() / expected - 1.).abs() < 0.01);
+        assert!(sky.sun_direction().a_eq(&Vec3::new(0., 1., 1.).normalized()));
+        // brighter towards the sun, black below the horizon
+        assert!(sky.radiance(Vec3::new(0., 1., 1.2).normalized()).luminance() > sky.radiance(Vec3::new(0., 1., -1.2).normalized()).luminance());
+        assert_eq!(sky.radiance(-Vec3::Y).luminance(), 0.);
+        // a clear sky is blue
+        let c = sky.radiance(Vec3::Y);
+        assert!(c.b > c.r);
+    }
+
+    #[test]
+    fn sampling() {
+        // the irradiance of sampled directions matches integrating the background uniformly
+        let sky = SkyLight::new(30., 60., 3., 1., 0.);
+        let hit = Hit { normal: Vec3::new(0.3, 1., 0.2).normalized(), ..Hit::default() };
+        let mut sampler = Sampler::new(0, 0);
+        let n = 200000;
+        let uniform = (0..n).map(|_| {
+            let d = uniform_sphere(sampler.next_2d());
+            sky.background(d).luminance() * d.dot(&hit.normal).max(0.) * 4. * PI
+        }).sum::<f32>() / n as f32;
+        let sampled = irradiance(&sky, &hit, &mut sampler, n);
+        assert!((sampled / uniform - 1.).abs() < 0.02, "{} {}", sampled, uniform);
+        // the sun adds E = π L sin² of its angular radius when facing it
+        let with_sun = SkyLight::new(30., 60., 3., 1., 4.);
+        let hit = Hit { normal: with_sun.sun_direction(), ..Hit::default() };
+        let sky_only = irradiance(&sky, &hit, &mut sampler, n);
+        let sun = PI * with_sun.sun_radiance.luminance() * 2f32.to_radians().sin().powi(2);
+        let total = irradiance(&with_sun, &hit, &mut sampler, n);
+        assert!((total / (sky_only + sun) - 1.).abs() < 0.02, "{} {}", total, sky_only + sun);
+        assert!(with_sun.background(with_sun.sun_direction()).luminance() > 1000. * sky.background(sky.sun_direction()).luminance());
+    }
+}
@@ -0,0 +1,97 @@
+use std::f32::consts::PI;
+use crate::color::Color;
+use crate::geometry::Hit;
+use crate::lights::{LightSample, LightSource};
+use crate::math::{Frame, Vec2, Vec3, Vector};
+use crate::sampling::warp::{uniform_cone, uniform_cone_pdf};
+
+// emits outwards, so points inside get no light. Points outside only sample the cone of directions the sphere covers,
+// which is much better than sampling its whole surface
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SphereLight {
+    center: Vec3,
+    radius: f32,
+    radiance: Color,
+}
+
+impl SphereLight {
+    pub fn new(center: Vec3, radius: f32, radiance: Color) -> Self {
+        SphereLight {
+            center,
+            radius,
+            radiance,
+        }
+    }
+
+    pub fn area(&self) -> f32 {
+        4. * PI * self.radius * self.radius
+    }
+}
+
+impl LightSource for SphereLight {
+    fn sample(&self, hit: &Hit, u: Vec2) -> Option<LightSample> {
+        let to_center = self.center - hit.point;
+        let distance_squared = to_center.length_squared();
+        let r2 = self.radius * self.radius;
+        if distance_squared <= r2 {
+            // only the outside emits, like an emissive sphere that is seen from within
+            return None;
+        }
+        let distance = distance_squared.sqrt();
+        let cos_max = (1. - r2 / distance_squared).max(0.).sqrt();
+        let frame = Frame::new(to_center / distance);
+        let local = uniform_cone(u, cos_max);
+        // closest intersection of the sampled direction with the sphere
+        let cos_theta = local.z;
+        let sin2_theta = (1. - cos_theta * cos_theta).max(0.);
+        let light_distance = distance * cos_theta - (r2 - distance_squared * sin2_theta).max(0.).sqrt();
+        Some(LightSample {
+            radiance: self.radiance,
+            direction: frame.to_world(local),
+            distance: light_distance,
+            pdf: uniform_cone_pdf(cos_max),
+        })
+    }
+}
+
+#[cfg(test)]
+mod sphere_tests {
+    use std::f32::consts::PI;
+    use crate::color::Color;
+    use crate::geometry::{Geometry, Hit, Sphere};
+    use crate::lights::{irradiance, LightSource};
+    use crate::lights::sphere::SphereLight;
+    use crate::math::{Vec2, Vec3, Vector};
+    use crate::ray::Ray;
+    use crate::sampling::Sampler;
+
+    #[test]
+    fn above() {
+        // a sphere straight above gives E = L π sin²θ with θ its angular radius, the same as a point light of
+        // intensity L π r² at distance d: L π r² / d²
+        let light = SphereLight::new(Vec3::new(0., 4., 0.), 1., Color::WHITE);
+        let hit = Hit { normal: Vec3::Y, geometric_normal: Vec3::Y, ..Hit::default() };
+        let mut sampler = Sampler::new(0, 0);
+        for _ in 0..100 {
+            let s = light.sample(&hit, sampler.next_2d()).unwrap();
+            // the sampled point lies on the sphere
+            let point = hit.point + s.direction * s.distance;
+            assert!(((point - Vec3::new(0., 4., 0.)).length() - 1.).abs() < 1e-3);
+        }
+        let e = irradiance(&light, &hit, &mut sampler, 10000);
+        let expected = PI / 16.;
+        assert!((e - expected).abs() < 0.01 * expected, "{}", e);
+        // the sampled distance matches the geometry
+        let s = light.sample(&hit, sampler.next_2d()).unwrap();
+        let sphere = Sphere::new(Vec3::new(0., 4., 0.), 1.);
+        let d = sphere.intersect(&Ray::new(Vec3::ZERO, s.direction, None, None)).unwrap().distance;
+        assert!((d - s.distance).abs() < 1e-3);
+    }
+
+    #[test]
+    fn inside() {
+        let light = SphereLight::new(Vec3::ZERO, 2., Color::WHITE);
+        let hit = Hit { point: Vec3::new(0.5, 0., 0.), normal: Vec3::Y, geometric_normal: Vec3::Y, ..Hit::default() };
+        assert!(light.sample(&hit, Vec2::new(0.3, 0.6)).is_none());
+    }
+}
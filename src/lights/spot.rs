@@ -0,0 +1,90 @@
+use std::f32::consts::PI;
+use crate::color::Color;
+use crate::geometry::Hit;
+use crate::lights::{LightSample, LightSource};
+use crate::math::{Vec2, Vec3, Vector};
+
+// a point light that only shines into a cone. Towards the edge of the cone it fades out smoothly
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SpotLight {
+    pub position: Vec3,
+    // normalized axis of the cone
+    pub direction: Vec3,
+    // along the axis
+    pub intensity: Color,
+    // cosines of the angle where the light ends and where it starts to fade
+    cos_total: f32,
+    cos_falloff: f32,
+}
+
+impl SpotLight {
+    // angle is the half angle of the cone, up to pi, and falloff the width of the soft edge inside of it, both in
+    // radians. A falloff wider than the cone fades out all the way from the axis
+    pub fn new(position: Vec3, direction: Vec3, intensity: Color, angle: f32, falloff: f32) -> Self {
+        assert!(angle > 0. && angle <= PI, "the angle of a spot light has to be in (0, pi]");
+        assert!(falloff >= 0., "the falloff of a spot light must not be negative");
+        SpotLight {
+            position,
+            direction: direction.normalized(),
+            intensity,
+            cos_total: angle.cos(),
+            cos_falloff: (angle - falloff.min(angle)).cos(),
+        }
+    }
+
+    // 1 inside the inner cone, 0 outside of the cone, smooth in between
+    fn falloff(&self, cos_theta: f32) -> f32 {
+        if cos_theta >= self.cos_falloff {
+            return 1.;
+        }
+        if cos_theta <= self.cos_total {
+            return 0.;
+        }
+        let t = (cos_theta - self.cos_total) / (self.cos_falloff - self.cos_total);
+        t * t * (3. - 2. * t)
+    }
+}
+
+impl LightSource for SpotLight {
+    fn sample(&self, hit: &Hit, _u: Vec2) -> Option<LightSample> {
+        let to_light = self.position - hit.point;
+        let distance_squared = to_light.length_squared();
+        let distance = distance_squared.sqrt();
+        let direction = to_light / distance;
+        let falloff = self.falloff(-direction.dot(&self.direction));
+        if falloff == 0. {
+            return None;
+        }
+        Some(LightSample {
+            radiance: self.intensity * (falloff / distance_squared),
+            direction,
+            distance,
+            pdf: 1.,
+        })
+    }
+}
+
+#[cfg(test)]
+mod spot_tests {
+    use crate::color::Color;
+    use crate::geometry::Hit;
+    use crate::lights::LightSource;
+    use crate::lights::spot::SpotLight;
+    use crate::math::{ApproxEq, Vec2, Vec3};
+
+    #[test]
+    fn cone() {
+        let light = SpotLight::new(Vec3::new(0., 2., 0.), -Vec3::Y, Color::WHITE, 45f32.to_radians(), 10f32.to_radians());
+        let at = |x: f32| light.sample(&Hit { point: Vec3::new(x, 0., 0.), ..Hit::default() }, Vec2::ZERO);
+        // straight below like a point light
+        let s = at(0.).unwrap();
+        assert_eq!(s.radiance, Color::new(0.25, 0.25, 0.25));
+        assert_eq!(s.direction, Vec3::Y);
+        // 30° off the axis is still inside the inner cone
+        assert!(at(2. * 30f32.to_radians().tan()).unwrap().radiance.r.a_eq(&(0.25 * 0.75)));
+        // halfway through the soft edge
+        let s = at(2. * 40f32.to_radians().tan()).unwrap();
+        assert!(s.radiance.r > 0. && s.radiance.r < 0.25 * 40f32.to_radians().cos().powi(2));
+        assert!(at(2. * 46f32.to_radians().tan()).is_none());
+    }
+}
@@ -0,0 +1,85 @@
+use crate::geometry::Hit;
+use crate::lights::{LightSample, LightSource};
+use crate::math::{Transform, Vec2, Vector};
+
+// an area light placed in the world by an affine transformation, used for emissive instances. The light is sampled in
+// object space and the sample moved into the world, which works for any transformation, even ones that distort it
+pub struct TransformedLight {
+    light: Box<dyn LightSource>,
+    // object to world
+    transform: Transform,
+}
+
+impl TransformedLight {
+    pub fn new(light: Box<dyn LightSource>, transform: Transform) -> Self {
+        TransformedLight {
+            light,
+            transform,
+        }
+    }
+}
+
+impl LightSource for TransformedLight {
+    fn sample(&self, hit: &Hit, u: Vec2) -> Option<LightSample> {
+        let inverse = self.transform.inverse();
+        let object_hit = Hit {
+            point: inverse.point(hit.point),
+            normal: inverse.normal(hit.normal),
+            geometric_normal: inverse.normal(hit.geometric_normal),
+            ..*hit
+        };
+        let sample = self.light.sample(&object_hit, u)?;
+        let direction = self.transform.vector(sample.direction);
+        let scale = direction.length();
+        // a linear map stretches the solid angle around a unit direction d by |det M| / |M d|³
+        Some(LightSample {
+            radiance: sample.radiance,
+            direction: direction / scale,
+            distance: sample.distance * scale,
+            pdf: sample.pdf * scale * scale * scale / self.transform.determinant().abs(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod transformed_tests {
+    use crate::color::Color;
+    use crate::geometry::Hit;
+    use crate::lights::{irradiance, LightSource};
+    use crate::lights::sphere::SphereLight;
+    use crate::lights::transformed::TransformedLight;
+    use crate::lights::triangle::TriangleLight;
+    use crate::math::{ApproxEq, Transform, Vec3};
+    use crate::sampling::Sampler;
+
+    #[test]
+    fn stretched_triangle() {
+        let transform = Transform::translate(Vec3::new(0., 3., 0.)) * Transform::rotate_y(0.4) * Transform::scale(Vec3::new(2., 1., 3.));
+        let v = [Vec3::new(0., 0., 0.), Vec3::new(0., 0., 1.), Vec3::new(1., 0., 0.)];
+        let local = TriangleLight::new(v[0], v[1], v[2], Color::WHITE);
+        let light = TransformedLight::new(Box::new(local), transform);
+        let [w0, w1, w2] = v.map(|p| transform.point(p));
+        let expected = TriangleLight::new(w0, w1, w2, Color::WHITE);
+        let hit = Hit { point: Vec3::new(0.5, 0., 0.5), normal: Vec3::Y, geometric_normal: Vec3::Y, ..Hit::default() };
+        let mut sampler = Sampler::new(0, 0);
+        let s = light.sample(&hit, sampler.next_2d()).unwrap();
+        // the sampled point lies on the transformed triangle
+        let p = hit.point + s.direction * s.distance;
+        assert!(p.y.a_eq(&3.));
+        let a = irradiance(&light, &hit, &mut sampler, 40000);
+        let b = irradiance(&expected, &hit, &mut sampler, 40000);
+        assert!((a - b).abs() < 0.01 * b, "{} {}", a, b);
+    }
+
+    #[test]
+    fn scaled_sphere() {
+        let light = TransformedLight::new(Box::new(SphereLight::new(Vec3::ZERO, 1., Color::WHITE)),
+                                          Transform::translate(Vec3::new(1., 5., 0.)) * Transform::scale(Vec3::from(2.)));
+        let expected = SphereLight::new(Vec3::new(1., 5., 0.), 2., Color::WHITE);
+        let hit = Hit { normal: Vec3::Y, geometric_normal: Vec3::Y, ..Hit::default() };
+        let mut sampler = Sampler::new(1, 0);
+        let a = irradiance(&light, &hit, &mut sampler, 40000);
+        let b = irradiance(&expected, &hit, &mut sampler, 40000);
+        assert!((a - b).abs() < 0.01 * b, "{} {}", a, b);
+    }
+}
@@ -0,0 +1,75 @@
+use crate::color::Color;
+use crate::geometry::Hit;
+use crate::lights::{area_sample, LightSample, LightSource};
+use crate::math::{Vec2, Vec3, Vector};
+use crate::sampling::warp::uniform_triangle;
+
+// uses the winding of Triangle, so it emits to the side of (v2 - v0) x (v1 - v0)
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TriangleLight {
+    vertices: [Vec3; 3],
+    normal: Vec3,
+    area: f32,
+    radiance: Color,
+}
+
+impl TriangleLight {
+    pub fn new(v0: Vec3, v1: Vec3, v2: Vec3, radiance: Color) -> Self {
+        let cross = (v2 - v0).cross(&(v1 - v0));
+        TriangleLight {
+            vertices: [v0, v1, v2],
+            normal: cross.normalized(),
+            area: cross.length() / 2.,
+            radiance,
+        }
+    }
+
+    pub fn area(&self) -> f32 {
+        self.area
+    }
+
+    pub fn normal(&self) -> Vec3 {
+        self.normal
+    }
+
+    pub fn radiance(&self) -> Color {
+        self.radiance
+    }
+
+    // uniformly distributed over the triangle
+    pub(crate) fn sample_point(&self, u: Vec2) -> Vec3 {
+        let b = uniform_triangle(u);
+        let [v0, v1, v2] = self.vertices;
+        v0 * (1. - b.x - b.y) + v1 * b.x + v2 * b.y
+    }
+}
+
+impl LightSource for TriangleLight {
+    fn sample(&self, hit: &Hit, u: Vec2) -> Option<LightSample> {
+        area_sample(hit, self.sample_point(u), self.normal, 1. / self.area, self.radiance)
+    }
+}
+
+#[cfg(test)]
+mod triangle_tests {
+    use crate::color::Color;
+    use crate::geometry::Hit;
+    use crate::lights::irradiance;
+    use crate::lights::rectangle::RectangleLight;
+    use crate::lights::triangle::TriangleLight;
+    use crate::math::Vec3;
+    use crate::sampling::Sampler;
+
+    #[test]
+    fn matches_rectangle() {
+        // two triangles cover the same square as the rectangle light
+        let (a, b, c, d) = (Vec3::new(-1., 1., -1.), Vec3::new(-1., 1., 1.), Vec3::new(1., 1., 1.), Vec3::new(1., 1., -1.));
+        let rectangle = RectangleLight::new(a, d - a, b - a, Color::WHITE);
+        let triangles = [TriangleLight::new(a, b, d, Color::WHITE), TriangleLight::new(b, c, d, Color::WHITE)];
+        let hit = Hit { point: Vec3::new(0.3, 0., 0.2), normal: Vec3::Y, geometric_normal: Vec3::Y, ..Hit::default() };
+        let mut sampler = Sampler::new(0, 0);
+        let expected = irradiance(&rectangle, &hit, &mut sampler, 20000);
+        let actual: f32 = triangles.iter().map(|t| irradiance(t, &hit, &mut sampler, 20000)).sum();
+        assert!((expected - actual).abs() < 0.01 * expected, "{} {}", expected, actual);
+    }
+}
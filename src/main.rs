@@ -1,16 +1,7 @@
 #![allow(dead_code)]
-use image::{ImageBuffer};
+use std::process::exit;
 
-use crate::camera::{Camera, PerspectiveCamera};
-use crate::color::Color;
-use crate::geometry::{Aabb, Sphere, Triangle};
-use crate::groups::{Group, GroupContent};
-use crate::groups::simple_group::SimpleGroup;
-use crate::integrators::Integrator;
-use crate::integrators::ray_trace::RayTraceIntegrator;
-use crate::lights::point::PointLight;
-use crate::math::{Vec2, Vec3};
-use crate::world::World;
+use crate::scene::Scene;
 
 pub mod geometry;
 pub mod math;
@@ -22,63 +13,31 @@ pub mod groups;
 pub mod lights;
 pub mod materials;
 pub mod world;
-
+pub mod render;
+pub mod sampling;
+pub mod film;
+pub mod filters;
+pub mod scene;
+pub mod bvh;
+
+// usage: rayst [scene file] [output image]
 fn main() {
-    let resolution = (900, 900);
-    let mut img = ImageBuffer::new(resolution.0, resolution.1);
-
-    let mut world = World{
-        geometry: Box::new(SimpleGroup::new()),
-        materials: vec![],
-        lights: vec![],
+    let args: Vec<String> = std::env::args().collect();
+    let path = args.get(1).map(String::as_str).unwrap_or("scenes/cornell.scene");
+    let scene = match Scene::load(path) {
+        Ok(scene) => scene,
+        Err(e) => {
+            eprintln!("{}: {}", path, e);
+            exit(1);
+        }
     };
+    let output = args.get(2).cloned()
+        .or_else(|| scene.output.clone())
+        .unwrap_or_else(|| "test.png".to_string());
 
-    let cam = PerspectiveCamera::new(Vec3::new(278., 273., -800.), Vec3::new(0., 0., 1.), Vec3::new(0., 1., 0.), 1., 45.);
-    // let cam = PerspectiveCamera::new(Vec3::new(2., 2., 2.), Vec3::new(-1., -1., -1.), Vec3::new(0., 0., 1.), 1., 90.);
-    // let cam = OrthographicCamera::new(Vec3::new(-10., 0., 0.), Vec3::new(1., 0., 0.), Vec3::new(0., 1., 0.), Vec2::new(2., 2.));
-    let sphere1 = Sphere::new(Vec3::new(300., 0., 200.), 100.);
-    world.geometry.push(GroupContent::new(Box::new(sphere1), None));
-    let box1 = Aabb::new(Vec3::new(100., 500., 300.), Vec3::new(400., 400., 400.));
-    world.geometry.push(GroupContent::new(Box::new(box1), None));
-    build_cornell_box(&mut *world.geometry);
-
-    world.lights.push(Box::new(PointLight::new(Vec3::new(250., 400., 150.), Color::new(200000., 150000., 100000.))));
-
-    let mut integrator = RayTraceIntegrator {
-        world: &world
-    };
-
-
-    for (x, y, pixel) in img.enumerate_pixels_mut() {
-        let ray = cam.at(Vec2::new(x as f32 / resolution.0 as f32, y as f32 / resolution.1 as f32));
-        let col = integrator.li(&ray);
-        *pixel = image::Rgb(col.to_u8());
-    }
+    let integrator = scene.integrator();
+    let img = scene.renderer.render(scene.camera.as_ref(), integrator.as_ref());
 
-    // integrators::simple_shade::intersect(&sphere, &Ray {});
-
-    img.save("test.png").unwrap();
-    println!("image written!");
+    img.save(&output).unwrap();
+    println!("image written to {}!", output);
 }
-
-
-
-
-fn build_cornell_box(world: &mut dyn Group) {
-    // FLOOR
-    world.push(GroupContent::new(Box::new(Triangle::new(Vec3::new(0., 0., 0.), Vec3::new(552.8, 0., 0.), Vec3::new(0., 0., 559.2))), None));
-    world.push(GroupContent::new(Box::new(Triangle::new(Vec3::new(0., 0., 559.2),Vec3::new(552.8, 0., 0.), Vec3::new(549.6, 0., 559.2))), None));
-    // CEILING
-    world.push(GroupContent::new(Box::new(Triangle::new(Vec3::new(0., 548.8, 0.), Vec3::new(0., 548.8, 559.2), Vec3::new(556., 548.8, 0.))), None));
-    world.push(GroupContent::new(Box::new(Triangle::new(Vec3::new(0., 548.8, 559.2),Vec3::new(556., 548.8, 559.2), Vec3::new(556., 548.8, 0.))), None));
-    // BACK WALL
-    world.push(GroupContent::new(Box::new(Triangle::new(Vec3::new(556., 548.8, 559.2), Vec3::new(0., 548.8, 559.2), Vec3::new(549.6, 0., 559.2))), None));
-    world.push(GroupContent::new(Box::new(Triangle::new(Vec3::new(0., 548.8, 559.2), Vec3::new(0., 0., 559.2), Vec3::new(549.6, 0., 559.2))), None));
-   // RIGHT WALL
-    world.push(GroupContent::new(Box::new(Triangle::new(Vec3::new(0., 548.8, 559.2), Vec3::new(0., 548.8, 0.), Vec3::new(0., 0., 559.2))), None));
-    world.push(GroupContent::new(Box::new(Triangle::new(Vec3::new(0., 548.8, 0.), Vec3::new(0., 0., 0.), Vec3::new(0., 0., 559.2))), None));
-    // LEFT WALL
-    world.push(GroupContent::new(Box::new(Triangle::new(Vec3::new(556., 548.8, 0.), Vec3::new(556., 548.8, 559.2), Vec3::new(552.8, 0., 0.))), None));
-    world.push(GroupContent::new(Box::new(Triangle::new(Vec3::new(556., 548.8, 559.2), Vec3::new(549.6, 0., 559.2), Vec3::new(552.8, 0., 0.))), None));
-
-}
\ No newline at end of file
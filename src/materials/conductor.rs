@@ -0,0 +1,133 @@
+use crate::color::Color;
+use crate::geometry::Hit;
+use crate::materials::{fresnel, Material, same_hemisphere};
+use crate::materials::microfacet::Ggx;
+use crate::math::{Vec2, Vec3, Vector};
+
+// rough metal, described by its complex index of refraction eta + i k
+pub struct RoughConductor {
+    eta: Color,
+    k: Color,
+    distribution: Ggx,
+}
+
+impl RoughConductor {
+    // measured values at the wavelengths 650nm, 550nm and 450nm
+    pub const GOLD: (Color, Color) = (Color { r: 0.143, g: 0.374, b: 1.442 }, Color { r: 3.983, g: 2.385, b: 1.603 });
+    pub const COPPER: (Color, Color) = (Color { r: 0.200, g: 0.924, b: 1.102 }, Color { r: 3.912, g: 2.452, b: 2.142 });
+    pub const ALUMINIUM: (Color, Color) = (Color { r: 1.657, g: 0.880, b: 0.521 }, Color { r: 9.224, g: 6.270, b: 4.837 });
+
+    pub fn new(eta: Color, k: Color, roughness: f32) -> Self {
+        RoughConductor {
+            eta,
+            k,
+            distribution: Ggx::from_roughness(roughness),
+        }
+    }
+
+    // e.g. RoughConductor::preset(RoughConductor::GOLD, 0.2)
+    pub fn preset(ior: (Color, Color), roughness: f32) -> Self {
+        Self::new(ior.0, ior.1, roughness)
+    }
+}
+
+// metals are opaque and look the same from both sides, so everything is evaluated in the upper hemisphere
+fn to_upper(wo: Vec3, wi: Vec3) -> (Vec3, Vec3) {
+    if wo.z < 0. {
+        (-wo, -wi)
+    } else {
+        (wo, wi)
+    }
+}
+
+impl Material for RoughConductor {
+    fn eval(&self, wo: Vec3, wi: Vec3) -> Color {
+        if !same_hemisphere(wo, wi) {
+            return Color::BLACK;
+        }
+        let (wo, wi) = to_upper(wo, wi);
+        let wm = (wo + wi).normalized();
+        let f = fresnel::conductor(wo.dot(&wm).abs(), self.eta, self.k);
+        // the cosine of wi cancels out with the denominator of the microfacet model
+        f * (self.distribution.d(wm) * self.distribution.g(wo, wi) / (4. * wo.z))
+    }
+
+    fn sample(&self, wo: Vec3, _hit: &Hit, _uc: f32, u: Vec2) -> Option<(Vec3, Color, f32)> {
+        if wo.z == 0. {
+            return None;
+        }
+        let flip = wo.z < 0.;
+        let wo_up = if flip { -wo } else { wo };
+        let wm = self.distribution.sample_visible(wo_up, u);
+        let wi_up = fresnel::reflect_about(wo_up, wm);
+        if wi_up.z <= 0. {
+            return None;
+        }
+        let pdf = self.distribution.visible_pdf(wo_up, wm) / (4. * wo_up.dot(&wm).abs());
+        if pdf <= 0. {
+            return None;
+        }
+        let wi = if flip { -wi_up } else { wi_up };
+        Some((wi, self.eval(wo, wi) / pdf, pdf))
+    }
+
+    fn pdf(&self, wo: Vec3, wi: Vec3) -> f32 {
+        if !same_hemisphere(wo, wi) {
+            return 0.;
+        }
+        let (wo, wi) = to_upper(wo, wi);
+        let wm = (wo + wi).normalized();
+        self.distribution.visible_pdf(wo, wm) / (4. * wo.dot(&wm).abs())
+    }
+}
+
+#[cfg(test)]
+mod conductor_tests {
+    use crate::color::Color;
+    use crate::geometry::Hit;
+    use crate::materials::conductor::RoughConductor;
+    use crate::materials::Material;
+    use crate::math::{Vec3, Vector};
+    use crate::sampling::Sampler;
+    use crate::sampling::warp::{uniform_sphere, uniform_sphere_pdf};
+
+    #[test]
+    fn sample_matches_eval_and_pdf() {
+        let m = RoughConductor::preset(RoughConductor::COPPER, 0.4);
+        let mut sampler = Sampler::new(1, 0);
+        for wo in [Vec3::new(0.3, 0.1, 0.8).normalized(), Vec3::new(-0.7, 0.1, -0.3).normalized()] {
+            for _ in 0..200 {
+                let Some((wi, weight, pdf)) = m.sample(wo, &Hit::default(), sampler.next_f32(), sampler.next_2d()) else {
+                    continue;
+                };
+                assert!(wi.z * wo.z > 0.);
+                assert!((pdf - m.pdf(wo, wi)).abs() < 1e-3 * pdf.max(1.));
+                let expected = m.eval(wo, wi) / pdf;
+                assert!((weight.g - expected.g).abs() < 1e-3);
+            }
+        }
+    }
+
+    #[test]
+    fn unbiased_sampling() {
+        // the average sample weight is the directional albedo, which can't exceed 1
+        let m = RoughConductor::new(Color::new(0.2, 0.2, 0.2), Color::new(8., 8., 8.), 0.8);
+        let mut sampler = Sampler::new(2, 0);
+        let wo = Vec3::new(0.5, 0., 0.5).normalized();
+        let mut sum = 0.;
+        for _ in 0..10000 {
+            if let Some((_, weight, _)) = m.sample(wo, &Hit::default(), sampler.next_f32(), sampler.next_2d()) {
+                sum += weight.r;
+            }
+        }
+        let albedo = sum / 10000.;
+        // brute force the same integral with uniformly distributed directions
+        let mut reference = 0.;
+        for _ in 0..100000 {
+            let wi = uniform_sphere(sampler.next_2d());
+            reference += m.eval(wo, wi).r / uniform_sphere_pdf();
+        }
+        let reference = reference / 100000.;
+        assert!(albedo <= 1. && (albedo - reference).abs() < 0.03, "{} {}", albedo, reference);
+    }
+}
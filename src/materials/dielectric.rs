@@ -0,0 +1,82 @@
+use crate::color::Color;
+use crate::geometry::Hit;
+use crate::materials::{fresnel, Material};
+use crate::math::{Vec2, Vec3};
+
+// smooth boundary of a transparent medium like glass or water, surrounded by vacuum
+pub struct Dielectric {
+    // index of refraction of the inside
+    ior: f32
+}
+
+impl Dielectric {
+    pub fn new(ior: f32) -> Self {
+        Dielectric {
+            ior
+        }
+    }
+}
+
+impl Material for Dielectric {
+    fn eval(&self, _wo: Vec3, _wi: Vec3) -> Color {
+        Color::BLACK
+    }
+
+    fn sample(&self, wo: Vec3, hit: &Hit, uc: f32, _u: Vec2) -> Option<(Vec3, Color, f32)> {
+        if wo.z == 0. {
+            return None;
+        }
+        // coming from the outside we enter the medium, otherwise we leave it
+        let eta = if hit.front_face { self.ior } else { 1. / self.ior };
+        // choose between reflection and refraction proportional to the fresnel reflectance
+        let reflectance = fresnel::dielectric(wo.z.abs(), eta);
+        if uc < reflectance {
+            return Some((fresnel::reflect(wo), Color::WHITE, reflectance));
+        }
+        // reflectance is 1 in case of total internal reflection, so this can't fail
+        let wi = fresnel::refract(wo, eta)?;
+        // radiance gets compressed into a smaller solid angle when entering a denser medium
+        Some((wi, Color::WHITE / (eta * eta), 1. - reflectance))
+    }
+
+    fn pdf(&self, _wo: Vec3, _wi: Vec3) -> f32 {
+        0.
+    }
+
+    fn is_specular(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod dielectric_tests {
+    use crate::geometry::Hit;
+    use crate::materials::dielectric::Dielectric;
+    use crate::materials::Material;
+    use crate::math::{ApproxEq, Vec2, Vec3};
+
+    #[test]
+    fn reflect_or_refract() {
+        let glass = Dielectric::new(1.5);
+        let outside = Hit { front_face: true, ..Hit::default() };
+        let wo = Vec3::new(0., 0., 1.);
+        // 4% of the light gets reflected at normal incidence
+        let (wi, _, pdf) = glass.sample(wo, &outside, 0.01, Vec2::ZERO).unwrap();
+        assert_eq!(wi, wo);
+        assert!(pdf.a_eq(&0.04));
+        let (wi, weight, pdf) = glass.sample(wo, &outside, 0.5, Vec2::ZERO).unwrap();
+        assert!(wi.a_eq(&-wo));
+        assert!(pdf.a_eq(&0.96));
+        assert!(weight.r.a_eq(&(1. / 2.25)));
+    }
+
+    #[test]
+    fn total_internal_reflection() {
+        let glass = Dielectric::new(1.5);
+        let inside = Hit { front_face: false, ..Hit::default() };
+        let wo = Vec3::new(0.8, 0., -0.6);
+        let (wi, _, pdf) = glass.sample(wo, &inside, 0.99, Vec2::ZERO).unwrap();
+        assert_eq!(wi, Vec3::new(-0.8, 0., -0.6));
+        assert_eq!(pdf, 1.);
+    }
+}
@@ -0,0 +1,35 @@
+use crate::color::Color;
+use crate::geometry::Hit;
+use crate::materials::Material;
+use crate::math::{Vec2, Vec3};
+
+// turns the surface into a light source. It emits from its outside only and doesn't reflect anything
+pub struct Emissive {
+    radiance: Color
+}
+
+impl Emissive {
+    pub fn new(radiance: Color) -> Self {
+        Emissive {
+            radiance
+        }
+    }
+}
+
+impl Material for Emissive {
+    fn eval(&self, _wo: Vec3, _wi: Vec3) -> Color {
+        Color::BLACK
+    }
+
+    fn sample(&self, _wo: Vec3, _hit: &Hit, _uc: f32, _u: Vec2) -> Option<(Vec3, Color, f32)> {
+        None
+    }
+
+    fn pdf(&self, _wo: Vec3, _wi: Vec3) -> f32 {
+        0.
+    }
+
+    fn emission(&self) -> Color {
+        self.radiance
+    }
+}
@@ -0,0 +1,131 @@
+use crate::color::Color;
+use crate::math::{Vec3, Vector};
+
+// unpolarized fresnel reflectance at the boundary between two dielectrics
+// cos_i: cosine between incident direction and normal (positive)
+// eta: ratio of the refractive indices n_transmitted / n_incident
+pub fn dielectric(cos_i: f32, eta: f32) -> f32 {
+    let cos_i = cos_i.clamp(0., 1.);
+    let sin2_t = (1. - cos_i * cos_i) / (eta * eta);
+    // total internal reflection
+    if sin2_t >= 1. {
+        return 1.;
+    }
+    let cos_t = (1. - sin2_t).sqrt();
+    let r_parallel = (eta * cos_i - cos_t) / (eta * cos_i + cos_t);
+    let r_perpendicular = (cos_i - eta * cos_t) / (cos_i + eta * cos_t);
+    (r_parallel * r_parallel + r_perpendicular * r_perpendicular) / 2.
+}
+
+// fresnel reflectance of a conductor with the complex index of refraction eta + i k, per color channel
+pub fn conductor(cos_i: f32, eta: Color, k: Color) -> Color {
+    Color::new(
+        conductor_channel(cos_i, eta.r, k.r),
+        conductor_channel(cos_i, eta.g, k.g),
+        conductor_channel(cos_i, eta.b, k.b),
+    )
+}
+
+fn conductor_channel(cos_i: f32, eta: f32, k: f32) -> f32 {
+    let cos2 = cos_i.clamp(0., 1.).powi(2);
+    let sin2 = 1. - cos2;
+    let t0 = eta * eta - k * k - sin2;
+    let a2_plus_b2 = (t0 * t0 + 4. * eta * eta * k * k).sqrt();
+    let t1 = a2_plus_b2 + cos2;
+    let a = (0.5 * (a2_plus_b2 + t0)).max(0.).sqrt();
+    let t2 = 2. * cos_i.clamp(0., 1.) * a;
+    let r_s = (t1 - t2) / (t1 + t2);
+    let t3 = cos2 * a2_plus_b2 + sin2 * sin2;
+    let t4 = t2 * sin2;
+    let r_p = r_s * (t3 - t4) / (t3 + t4);
+    0.5 * (r_p + r_s)
+}
+
+// mirrors a local direction on the normal (+z)
+pub fn reflect(w: Vec3) -> Vec3 {
+    Vec3::new(-w.x, -w.y, w.z)
+}
+
+// refracts a local direction through the surface. eta is n_transmitted / n_incident
+// for the side w lies on. None in case of total internal reflection
+pub fn refract(w: Vec3, eta: f32) -> Option<Vec3> {
+    let cos_i = w.z.abs();
+    let sin2_t = (1. - cos_i * cos_i).max(0.) / (eta * eta);
+    if sin2_t >= 1. {
+        return None;
+    }
+    let cos_t = (1. - sin2_t).sqrt();
+    Some(Vec3::new(-w.x / eta, -w.y / eta, -cos_t.copysign(w.z)))
+}
+
+// mirrors w on an arbitrary normal n
+pub fn reflect_about(w: Vec3, n: Vec3) -> Vec3 {
+    -w + n * 2. * w.dot(&n)
+}
+
+// refracts w through a surface with normal n. eta is n_transmitted / n_incident for the side n points to.
+// returns the direction and the eta relative to the side w lies on
+pub fn refract_about(w: Vec3, n: Vec3, eta: f32) -> Option<(Vec3, f32)> {
+    let mut cos_i = n.dot(&w);
+    let (n, eta) = if cos_i < 0. {
+        cos_i = -cos_i;
+        (-n, 1. / eta)
+    } else {
+        (n, eta)
+    };
+    let sin2_t = (1. - cos_i * cos_i).max(0.) / (eta * eta);
+    if sin2_t >= 1. {
+        return None;
+    }
+    let cos_t = (1. - sin2_t).sqrt();
+    Some((-w / eta + n * (cos_i / eta - cos_t), eta))
+}
+
+#[cfg(test)]
+mod fresnel_tests {
+    use crate::color::Color;
+    use crate::materials::fresnel::{conductor, dielectric, refract, refract_about};
+    use crate::math::{ApproxEq, Vec3, Vector};
+
+    #[test]
+    fn normal_incidence() {
+        // ((n - 1) / (n + 1))² for glass
+        assert!(dielectric(1., 1.5).a_eq(&0.04));
+        assert!(dielectric(1., 1.).a_eq(&0.));
+        assert!(dielectric(0., 1.5).a_eq(&1.));
+    }
+
+    #[test]
+    fn snells_law() {
+        let w = Vec3::new(0.6, 0., 0.8);
+        let t = refract(w, 1.5).unwrap();
+        assert!(t.length().a_eq(&1.));
+        assert!(t.z < 0.);
+        // n_i sin_i = n_t sin_t
+        assert!((0.6f32).a_eq(&(1.5 * t.x.abs())));
+        // leaving the denser medium at a flat angle reflects totally
+        assert!(refract(Vec3::new(0.8, 0., -0.6), 1. / 1.5).is_none());
+        assert_eq!(dielectric(0.6, 1. / 1.5), 1.);
+    }
+
+    #[test]
+    fn refract_about_matches_refract() {
+        let w = Vec3::new(0.6, 0., 0.8);
+        let (t, eta) = refract_about(w, Vec3::Z, 1.5).unwrap();
+        assert!(t.a_eq(&refract(w, 1.5).unwrap()));
+        assert_eq!(eta, 1.5);
+        let (t, eta) = refract_about(t, Vec3::Z, 1.5).unwrap();
+        assert!(t.a_eq(&w));
+        assert!(eta.a_eq(&(1. / 1.5)));
+    }
+
+    #[test]
+    fn conductor_limits() {
+        // without absorption the formula has to agree with the dielectric one
+        let r = conductor(0.7, Color::new(1.5, 1.5, 1.5), Color::BLACK);
+        assert!(r.r.a_eq(&dielectric(0.7, 1.5)));
+        // metals reflect almost everything at grazing angles
+        let r = conductor(0.001, Color::new(0.2, 0.9, 1.1), Color::new(3.9, 2.4, 2.1));
+        assert!(r.r > 0.99 && r.b > 0.99);
+    }
+}
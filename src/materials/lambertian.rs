@@ -1,13 +1,90 @@
+use std::f32::consts::PI;
 use crate::color::Color;
-use crate::materials::Material;
-use crate::math::{Vec3, Vector};
+use crate::geometry::Hit;
+use crate::materials::{Material, same_hemisphere};
+use crate::math::{Vec2, Vec3};
+use crate::sampling::warp::{cosine_hemisphere, cosine_hemisphere_pdf};
 
+// ideal diffuse reflector. Reflects on both sides of the surface
 pub struct Lambertian {
     color: Color
 }
 
+impl Lambertian {
+    // used for geometry without a material
+    pub const WHITE: Self = Lambertian {
+        color: Color::WHITE
+    };
+
+    pub fn new(color: Color) -> Self {
+        Lambertian {
+            color
+        }
+    }
+}
+
 impl Material for Lambertian {
-    fn brdf(&self, color_in: Color, light_in: Vec3, _light_out: Vec3, normal: Vec3) -> Color {
-        color_in * light_in.dot(&normal) * self.color
+    fn eval(&self, wo: Vec3, wi: Vec3) -> Color {
+        if !same_hemisphere(wo, wi) {
+            return Color::BLACK;
+        }
+        self.color * wi.z.abs() / PI
+    }
+
+    fn sample(&self, wo: Vec3, _hit: &Hit, _uc: f32, u: Vec2) -> Option<(Vec3, Color, f32)> {
+        if wo.z == 0. {
+            return None;
+        }
+        let mut wi = cosine_hemisphere(u);
+        if wo.z < 0. {
+            wi.z = -wi.z;
+        }
+        let pdf = cosine_hemisphere_pdf(wi.z.abs());
+        if pdf <= 0. {
+            return None;
+        }
+        // the cosine cancels out with the pdf
+        Some((wi, self.color, pdf))
+    }
+
+    fn pdf(&self, wo: Vec3, wi: Vec3) -> f32 {
+        if !same_hemisphere(wo, wi) {
+            return 0.;
+        }
+        cosine_hemisphere_pdf(wi.z.abs())
+    }
+}
+
+#[cfg(test)]
+mod lambertian_tests {
+    use crate::color::Color;
+    use crate::geometry::Hit;
+    use crate::materials::lambertian::Lambertian;
+    use crate::materials::Material;
+    use crate::math::{ApproxEq, Vec3, Vector};
+    use crate::sampling::Sampler;
+
+    #[test]
+    fn sample_matches_eval_and_pdf() {
+        let m = Lambertian::new(Color::new(0.5, 0.25, 1.));
+        let mut sampler = Sampler::new(0, 0);
+        for wo in [Vec3::new(0.3, 0.1, 0.8).normalized(), Vec3::new(0.3, 0.1, -0.8).normalized()] {
+            for _ in 0..100 {
+                let (wi, weight, pdf) = m.sample(wo, &Hit::default(), sampler.next_f32(), sampler.next_2d()).unwrap();
+                assert!(wi.z * wo.z > 0.);
+                assert!(pdf.a_eq(&m.pdf(wo, wi)));
+                let expected = m.eval(wo, wi) / pdf;
+                assert!(weight.r.a_eq(&expected.r) && weight.g.a_eq(&expected.g) && weight.b.a_eq(&expected.b));
+            }
+        }
+    }
+
+    #[test]
+    fn no_transmission() {
+        let m = Lambertian::new(Color::WHITE);
+        let wo = Vec3::new(0., 0., 1.);
+        let wi = Vec3::new(0., 0.6, -0.8);
+        assert_eq!(m.eval(wo, wi), Color::BLACK);
+        assert_eq!(m.pdf(wo, wi), 0.);
     }
 }
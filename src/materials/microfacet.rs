@@ -0,0 +1,124 @@
+use std::f32::consts::PI;
+use crate::math::{Vec2, Vec3, Vector};
+
+// isotropic GGX / Trowbridge-Reitz distribution of microfacet normals in the local frame
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Ggx {
+    alpha: f32,
+}
+
+impl Ggx {
+    // perceptually more linear than the alpha parameter: alpha = roughness²
+    pub fn from_roughness(roughness: f32) -> Self {
+        Ggx {
+            // a perfectly smooth surface would need a delta distribution
+            alpha: (roughness * roughness).max(1e-4),
+        }
+    }
+
+    // density of microfacets with normal wm, projected onto the macro surface
+    pub fn d(&self, wm: Vec3) -> f32 {
+        let cos2 = wm.z * wm.z;
+        if cos2 <= 0. {
+            return 0.;
+        }
+        let tan2 = (1. - cos2) / cos2;
+        let e = 1. + tan2 / (self.alpha * self.alpha);
+        1. / (PI * self.alpha * self.alpha * cos2 * cos2 * e * e)
+    }
+
+    // Smith's auxiliary function
+    fn lambda(&self, w: Vec3) -> f32 {
+        let cos2 = w.z * w.z;
+        if cos2 <= 0. {
+            return f32::INFINITY;
+        }
+        let tan2 = (1. - cos2) / cos2;
+        ((1. + self.alpha * self.alpha * tan2).sqrt() - 1.) / 2.
+    }
+
+    // fraction of microfacets visible from w
+    pub fn g1(&self, w: Vec3) -> f32 {
+        1. / (1. + self.lambda(w))
+    }
+
+    // fraction of microfacets visible from both directions (height correlated)
+    pub fn g(&self, wo: Vec3, wi: Vec3) -> f32 {
+        1. / (1. + self.lambda(wo) + self.lambda(wi))
+    }
+
+    // density of the normals visible from w
+    pub fn visible_pdf(&self, w: Vec3, wm: Vec3) -> f32 {
+        if w.z == 0. {
+            return 0.;
+        }
+        self.g1(w) / w.z.abs() * self.d(wm) * w.dot(&wm).abs()
+    }
+
+    // samples a normal from the distribution of normals visible from w (Heitz 2018).
+    // the result always lies in the upper hemisphere
+    pub fn sample_visible(&self, w: Vec3, u: Vec2) -> Vec3 {
+        // stretch the view direction, so the distribution becomes a hemisphere
+        let mut wh = Vec3::new(self.alpha * w.x, self.alpha * w.y, w.z).normalized();
+        if wh.z < 0. {
+            wh = -wh;
+        }
+        let length2 = wh.x * wh.x + wh.y * wh.y;
+        let t1 = if length2 > 0. {
+            Vec3::new(-wh.y, wh.x, 0.) / length2.sqrt()
+        } else {
+            Vec3::X
+        };
+        let t2 = wh.cross(&t1);
+        // uniform disk sample, warped to the projected area of the visible hemisphere
+        let r = u.x.sqrt();
+        let phi = 2. * PI * u.y;
+        let p1 = r * phi.cos();
+        let s = 0.5 * (1. + wh.z);
+        let p2 = (1. - s) * (1. - p1 * p1).max(0.).sqrt() + s * r * phi.sin();
+        let nh = t1 * p1 + t2 * p2 + wh * (1. - p1 * p1 - p2 * p2).max(0.).sqrt();
+        // unstretch
+        Vec3::new(self.alpha * nh.x, self.alpha * nh.y, nh.z.max(1e-6)).normalized()
+    }
+}
+
+#[cfg(test)]
+mod microfacet_tests {
+    use std::f32::consts::PI;
+    use crate::materials::microfacet::Ggx;
+    use crate::math::{Vec3, Vector};
+    use crate::sampling::Sampler;
+    use crate::sampling::warp::uniform_sphere;
+
+    #[test]
+    fn normalized() {
+        // the projected microfacet area has to add up to the macro surface area
+        for roughness in [0.3, 0.6, 1.] {
+            let ggx = Ggx::from_roughness(roughness);
+            let mut sampler = Sampler::new(5, 0);
+            let n = 200000;
+            let mut sum = 0.;
+            for _ in 0..n {
+                let wm = uniform_sphere(sampler.next_2d());
+                if wm.z > 0. {
+                    sum += ggx.d(wm) * wm.z;
+                }
+            }
+            let integral = sum * 4. * PI / n as f32;
+            assert!((integral - 1.).abs() < 0.05, "{}", integral);
+        }
+    }
+
+    #[test]
+    fn visible_normals_face_the_viewer() {
+        let ggx = Ggx::from_roughness(0.5);
+        let mut sampler = Sampler::new(6, 0);
+        let w = Vec3::new(0.5, 0.2, 0.4).normalized();
+        for _ in 0..1000 {
+            let wm = ggx.sample_visible(w, sampler.next_2d());
+            assert!(wm.z > 0.);
+            assert!(wm.dot(&w) >= -1e-4);
+            assert!(ggx.visible_pdf(w, wm) > 0.);
+        }
+    }
+}
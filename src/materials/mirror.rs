@@ -0,0 +1,60 @@
+use crate::color::Color;
+use crate::geometry::Hit;
+use crate::materials::Material;
+use crate::materials::fresnel::reflect;
+use crate::math::{Vec2, Vec3};
+
+// perfectly smooth specular reflector
+pub struct Mirror {
+    color: Color
+}
+
+impl Mirror {
+    pub fn new(color: Color) -> Self {
+        Mirror {
+            color
+        }
+    }
+}
+
+impl Material for Mirror {
+    // a delta distribution can't be evaluated for any given pair of directions
+    fn eval(&self, _wo: Vec3, _wi: Vec3) -> Color {
+        Color::BLACK
+    }
+
+    fn sample(&self, wo: Vec3, _hit: &Hit, _uc: f32, _u: Vec2) -> Option<(Vec3, Color, f32)> {
+        if wo.z == 0. {
+            return None;
+        }
+        Some((reflect(wo), self.color, 1.))
+    }
+
+    fn pdf(&self, _wo: Vec3, _wi: Vec3) -> f32 {
+        0.
+    }
+
+    fn is_specular(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod mirror_tests {
+    use crate::color::Color;
+    use crate::geometry::Hit;
+    use crate::materials::Material;
+    use crate::materials::mirror::Mirror;
+    use crate::math::{Vec2, Vec3};
+
+    #[test]
+    fn reflects() {
+        let m = Mirror::new(Color::WHITE);
+        let (wi, weight, _) = m.sample(Vec3::new(0.6, 0., 0.8), &Hit::default(), 0., Vec2::ZERO).unwrap();
+        assert_eq!(wi, Vec3::new(-0.6, 0., 0.8));
+        assert_eq!(weight, Color::WHITE);
+        // seen from behind
+        let (wi, _, _) = m.sample(Vec3::new(0.6, 0., -0.8), &Hit::default(), 0., Vec2::ZERO).unwrap();
+        assert_eq!(wi, Vec3::new(-0.6, 0., -0.8));
+    }
+}
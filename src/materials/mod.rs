@@ -1,8 +1,42 @@
 use crate::color::Color;
-use crate::math::Vec3;
+use crate::geometry::Hit;
+use crate::math::{Vec2, Vec3};
 
 pub mod lambertian;
+pub mod mirror;
+pub mod dielectric;
+pub mod fresnel;
+pub mod microfacet;
+pub mod conductor;
+pub mod rough_dielectric;
+pub mod emissive;
 
-pub trait Material {
-    fn brdf(&self, color_in: Color, light_in: Vec3, light_out: Vec3, normal: Vec3) -> Color;
+// index of a material in World::materials
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct MaterialId(pub usize);
+
+// all directions are given in the local shading frame: the surface normal is +z,
+// wo points towards the viewer and wi towards the light, both away from the surface.
+// the normal is not flipped towards the viewer, so a negative wo.z means the surface is seen from behind
+pub trait Material: Send + Sync {
+    // bsdf value times the cosine term |wi.z|
+    fn eval(&self, wo: Vec3, wi: Vec3) -> Color;
+    // importance samples an incoming direction. uc selects between lobes, u picks a direction within the lobe
+    // returns wi, the sample weight eval(wo, wi) / pdf and the pdf (solid angle measure)
+    fn sample(&self, wo: Vec3, hit: &Hit, uc: f32, u: Vec2) -> Option<(Vec3, Color, f32)>;
+    // density with which sample() produces wi
+    fn pdf(&self, wo: Vec3, wi: Vec3) -> f32;
+    // true for delta distributions, which can only be sampled. eval and pdf return 0 for them
+    fn is_specular(&self) -> bool {
+        false
+    }
+    // radiance the surface emits on its own. Only the outside emits, so it is only visible where front_face is set
+    fn emission(&self) -> Color {
+        Color::BLACK
+    }
+}
+
+// true if both directions are on the same side of the surface
+pub(crate) fn same_hemisphere(wo: Vec3, wi: Vec3) -> bool {
+    wo.z * wi.z > 0.
 }
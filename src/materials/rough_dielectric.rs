@@ -0,0 +1,153 @@
+use crate::color::Color;
+use crate::geometry::Hit;
+use crate::materials::{fresnel, Material};
+use crate::materials::microfacet::Ggx;
+use crate::math::{Vec2, Vec3, Vector};
+
+// frosted glass: a dielectric boundary with a GGX distributed microsurface (Walter et al. 2007)
+pub struct RoughDielectric {
+    // index of refraction of the inside
+    ior: f32,
+    distribution: Ggx,
+}
+
+impl RoughDielectric {
+    pub fn new(ior: f32, roughness: f32) -> Self {
+        RoughDielectric {
+            ior,
+            distribution: Ggx::from_roughness(roughness),
+        }
+    }
+
+    // fresnel reflectance on a microfacet with normal wm (upper hemisphere)
+    fn fresnel(&self, wo: Vec3, wm: Vec3) -> f32 {
+        let cos = wo.dot(&wm);
+        if cos >= 0. {
+            fresnel::dielectric(cos, self.ior)
+        } else {
+            fresnel::dielectric(-cos, 1. / self.ior)
+        }
+    }
+
+    // microfacet normal that maps wo onto wi, facing the upper hemisphere. the second value is the relative
+    // index of refraction for transmission (1 for reflection). None for configurations that can't occur
+    fn half_vector(&self, wo: Vec3, wi: Vec3) -> Option<(Vec3, f32)> {
+        if wo.z == 0. || wi.z == 0. {
+            return None;
+        }
+        let reflect = wo.z * wi.z > 0.;
+        let etap = if reflect {
+            1.
+        } else if wo.z > 0. {
+            self.ior
+        } else {
+            1. / self.ior
+        };
+        let wm = wi * etap + wo;
+        if wm.length_squared() == 0. {
+            return None;
+        }
+        let mut wm = wm.normalized();
+        if wm.z < 0. {
+            wm = -wm;
+        }
+        // back facing microfacets don't contribute
+        if wm.dot(&wi) * wi.z < 0. || wm.dot(&wo) * wo.z < 0. {
+            return None;
+        }
+        Some((wm, etap))
+    }
+}
+
+impl Material for RoughDielectric {
+    fn eval(&self, wo: Vec3, wi: Vec3) -> Color {
+        let Some((wm, etap)) = self.half_vector(wo, wi) else {
+            return Color::BLACK;
+        };
+        let f = self.fresnel(wo, wm);
+        let d = self.distribution.d(wm);
+        let g = self.distribution.g(wo, wi);
+        // all values include the cosine of wi, which cancels out with the microfacet denominators
+        if etap == 1. {
+            Color::WHITE * (d * g * f / (4. * wo.z.abs()))
+        } else {
+            let denom = wi.dot(&wm) + wo.dot(&wm) / etap;
+            let ft = d * (1. - f) * g * (wi.dot(&wm) * wo.dot(&wm) / (wo.z * denom * denom)).abs();
+            // radiance scaling, as for the smooth dielectric
+            Color::WHITE * (ft / (etap * etap))
+        }
+    }
+
+    fn sample(&self, wo: Vec3, _hit: &Hit, uc: f32, u: Vec2) -> Option<(Vec3, Color, f32)> {
+        if wo.z == 0. {
+            return None;
+        }
+        let wm = self.distribution.sample_visible(wo, u);
+        let r = self.fresnel(wo, wm);
+        let wi = if uc < r {
+            let wi = fresnel::reflect_about(wo, wm);
+            if wi.z * wo.z <= 0. {
+                return None;
+            }
+            wi
+        } else {
+            let (wi, _) = fresnel::refract_about(wo, wm, self.ior)?;
+            if wi.z * wo.z >= 0. {
+                return None;
+            }
+            wi
+        };
+        let pdf = self.pdf(wo, wi);
+        if pdf <= 0. {
+            return None;
+        }
+        Some((wi, self.eval(wo, wi) / pdf, pdf))
+    }
+
+    fn pdf(&self, wo: Vec3, wi: Vec3) -> f32 {
+        let Some((wm, etap)) = self.half_vector(wo, wi) else {
+            return 0.;
+        };
+        let r = self.fresnel(wo, wm);
+        let visible = self.distribution.visible_pdf(wo, wm);
+        if etap == 1. {
+            visible / (4. * wo.dot(&wm).abs()) * r
+        } else {
+            let denom = wi.dot(&wm) + wo.dot(&wm) / etap;
+            // change of variables from the microfacet normal to the refracted direction
+            let dwm_dwi = wi.dot(&wm).abs() / (denom * denom);
+            visible * dwm_dwi * (1. - r)
+        }
+    }
+}
+
+#[cfg(test)]
+mod rough_dielectric_tests {
+    use crate::geometry::Hit;
+    use crate::materials::Material;
+    use crate::materials::rough_dielectric::RoughDielectric;
+    use crate::math::{Vec3, Vector};
+    use crate::sampling::Sampler;
+
+    #[test]
+    fn sample_matches_eval_and_pdf() {
+        let m = RoughDielectric::new(1.5, 0.3);
+        let mut sampler = Sampler::new(3, 0);
+        let mut transmitted = 0;
+        for wo in [Vec3::new(0.3, 0.1, 0.8).normalized(), Vec3::new(-0.2, 0.1, -0.9).normalized()] {
+            for _ in 0..500 {
+                let Some((wi, weight, pdf)) = m.sample(wo, &Hit::default(), sampler.next_f32(), sampler.next_2d()) else {
+                    continue;
+                };
+                if wi.z * wo.z < 0. {
+                    transmitted += 1;
+                }
+                assert!((pdf - m.pdf(wo, wi)).abs() < 1e-3 * pdf.max(1.));
+                let expected = m.eval(wo, wi) / pdf;
+                assert!((weight.r - expected.r).abs() < 1e-3 * expected.r.max(1.));
+            }
+        }
+        // glass transmits most of the light
+        assert!(transmitted > 600);
+    }
+}
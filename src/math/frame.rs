@@ -0,0 +1,52 @@
+use crate::math::{Vec3, Vector};
+
+// orthonormal basis with n as the local z axis
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Frame {
+    pub s: Vec3,
+    pub t: Vec3,
+    pub n: Vec3,
+}
+
+impl Frame {
+    // n has to be normalized. branchless construction by Duff et al. 2017
+    pub fn new(n: Vec3) -> Self {
+        let sign = 1f32.copysign(n.z);
+        let a = -1. / (sign + n.z);
+        let b = n.x * n.y * a;
+        Frame {
+            s: Vec3::new(1. + sign * n.x * n.x * a, sign * b, -sign * n.x),
+            t: Vec3::new(b, sign + n.y * n.y * a, -n.y),
+            n,
+        }
+    }
+
+    pub fn to_local(&self, v: Vec3) -> Vec3 {
+        Vec3::new(v.dot(&self.s), v.dot(&self.t), v.dot(&self.n))
+    }
+
+    pub fn to_world(&self, v: Vec3) -> Vec3 {
+        self.s * v.x + self.t * v.y + self.n * v.z
+    }
+}
+
+#[cfg(test)]
+mod frame_tests {
+    use crate::math::{ApproxEq, Vec3, Vector};
+    use crate::math::frame::Frame;
+
+    #[test]
+    fn orthonormal() {
+        for n in [Vec3::X, -Vec3::Y, Vec3::Z, -Vec3::Z, Vec3::new(1., -2., 0.5).normalized()] {
+            let f = Frame::new(n);
+            assert!(f.s.dot(&f.t).a_eq(&0.));
+            assert!(f.s.dot(&f.n).a_eq(&0.));
+            assert!(f.t.dot(&f.n).a_eq(&0.));
+            assert!(f.s.length().a_eq(&1.));
+            assert!(f.t.length().a_eq(&1.));
+            let v = Vec3::new(0.3, -0.2, 0.9);
+            assert!(f.to_world(f.to_local(v)).a_eq(&v));
+            assert!(f.to_local(n).a_eq(&Vec3::Z));
+        }
+    }
+}
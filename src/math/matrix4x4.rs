@@ -1,6 +1,8 @@
 use std::ops::{Index, IndexMut, Mul};
-use crate::math::{Vec3, Vec4};
+use crate::math::{ApproxEq, Vec3, Vec4, Vector};
 
+// row major, the axes are the rows
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub struct Mat4 {
     x_axis: Vec4,
     y_axis: Vec4,
@@ -10,7 +12,7 @@ pub struct Mat4 {
 
 impl Mat4 {
 
-    fn new(x_axis: Vec4, y_axis: Vec4, z_axis: Vec4, w_axis: Vec4) -> Self {
+    pub fn new(x_axis: Vec4, y_axis: Vec4, z_axis: Vec4, w_axis: Vec4) -> Self {
         Self {
             x_axis,
             y_axis,
@@ -33,7 +35,14 @@ impl Mat4 {
         w_axis: Vec4::ONE,
     };
 
-    fn translate(by: Vec3) -> Self {
+    pub const IDENTITY: Self = Mat4 {
+        x_axis: Vec4 { x: 1., y: 0., z: 0., w: 0. },
+        y_axis: Vec4 { x: 0., y: 1., z: 0., w: 0. },
+        z_axis: Vec4 { x: 0., y: 0., z: 1., w: 0. },
+        w_axis: Vec4 { x: 0., y: 0., z: 0., w: 1. },
+    };
+
+    pub fn translate(by: Vec3) -> Self {
         Mat4 {
             x_axis: Vec4::new(1., 0., 0., by.x),
             y_axis: Vec4::new(0., 1., 0., by.y),
@@ -42,7 +51,7 @@ impl Mat4 {
         }
     }
 
-    fn scale(by: Vec3) -> Self {
+    pub fn scale(by: Vec3) -> Self {
         Mat4 {
             x_axis: Vec4::new(by.x, 0., 0., 0.),
             y_axis: Vec4::new(0., by.y, 0., 0.),
@@ -51,7 +60,7 @@ impl Mat4 {
         }
     }
 
-    fn mirror_x() -> Self {
+    pub fn mirror_x() -> Self {
         Mat4 {
             x_axis: Vec4::new(-1., 0., 0., 0.),
             y_axis: Vec4::new(0., 1., 0., 0.),
@@ -60,7 +69,7 @@ impl Mat4 {
         }
     }
 
-    fn mirror_y() -> Self {
+    pub fn mirror_y() -> Self {
         Mat4 {
             x_axis: Vec4::new(1., 0., 0., 0.),
             y_axis: Vec4::new(0., -1., 0., 0.),
@@ -69,7 +78,7 @@ impl Mat4 {
         }
     }
 
-    fn mirror_z() -> Self {
+    pub fn mirror_z() -> Self {
         Mat4 {
             x_axis: Vec4::new(1., 0., 0., 0.),
             y_axis: Vec4::new(0., 1., 0., 0.),
@@ -78,7 +87,7 @@ impl Mat4 {
         }
     }
 
-    fn mirror_0() -> Self {
+    pub fn mirror_0() -> Self {
         Mat4 {
             x_axis: Vec4::new(-1., 0., 0., 0.),
             y_axis: Vec4::new(0., -1., 0., 0.),
@@ -87,7 +96,7 @@ impl Mat4 {
         }
     }
 
-    fn shear(x_y: f32, x_z: f32, y_z: f32, y_x: f32, z_x: f32, z_y: f32) -> Self {
+    pub fn shear(x_y: f32, x_z: f32, y_z: f32, y_x: f32, z_x: f32, z_y: f32) -> Self {
         Mat4 {
             x_axis: Vec4::new(1., x_y, x_z, 0.),
             y_axis: Vec4::new(y_x, 1., y_z, 0.),
@@ -96,16 +105,16 @@ impl Mat4 {
         }
     }
 
-    fn rotate_x(by: f32) -> Self {
+    pub fn rotate_x(by: f32) -> Self {
         Mat4 {
             x_axis: Vec4::new(1., 0., 0., 0.),
             y_axis: Vec4::new(0., by.cos(), -by.sin(), 0.),
-            z_axis: Vec4::new(0., by.cos(), by.sin(), 0.),
+            z_axis: Vec4::new(0., by.sin(), by.cos(), 0.),
             w_axis: Vec4::new(0., 0., 0., 1.),
         }
     }
 
-    fn rotate_y(by: f32) -> Self {
+    pub fn rotate_y(by: f32) -> Self {
         Mat4 {
             x_axis: Vec4::new(by.cos(), 0., by.sin(), 0.),
             y_axis: Vec4::new(0., 1., 0., 0.),
@@ -114,15 +123,101 @@ impl Mat4 {
         }
     }
 
-    fn rotate_z(by: f32) -> Self {
+    pub fn rotate_z(by: f32) -> Self {
         Mat4 {
             x_axis: Vec4::new(by.cos(), -by.sin(), 0., 0.),
             y_axis: Vec4::new(by.sin(), by.cos(), 0., 0.),
-            z_axis: Vec4::new(0., 0., -1., 0.),
+            z_axis: Vec4::new(0., 0., 1., 0.),
+            w_axis: Vec4::new(0., 0., 0., 1.),
+        }
+    }
+
+    // counter clockwise rotation around an arbitrary axis, by in radians
+    pub fn rotate(axis: Vec3, by: f32) -> Self {
+        let a = axis.normalized();
+        let (sin, cos) = by.sin_cos();
+        let c = 1. - cos;
+        Mat4 {
+            x_axis: Vec4::new(a.x * a.x * c + cos, a.x * a.y * c - a.z * sin, a.x * a.z * c + a.y * sin, 0.),
+            y_axis: Vec4::new(a.y * a.x * c + a.z * sin, a.y * a.y * c + cos, a.y * a.z * c - a.x * sin, 0.),
+            z_axis: Vec4::new(a.z * a.x * c - a.y * sin, a.z * a.y * c + a.x * sin, a.z * a.z * c + cos, 0.),
+            w_axis: Vec4::new(0., 0., 0., 1.),
+        }
+    }
+
+    // moves an object from the origin to eye, so its +z axis looks at target and +y is as close to up as possible
+    pub fn look_at(eye: Vec3, target: Vec3, up: Vec3) -> Self {
+        let z = (target - eye).normalized();
+        let x = up.cross(&z).normalized();
+        let y = z.cross(&x);
+        Mat4 {
+            x_axis: Vec4::new(x.x, y.x, z.x, eye.x),
+            y_axis: Vec4::new(x.y, y.y, z.y, eye.y),
+            z_axis: Vec4::new(x.z, y.z, z.z, eye.z),
             w_axis: Vec4::new(0., 0., 0., 1.),
         }
     }
 
+    pub fn transpose(&self) -> Self {
+        let mut res = Self::ZERO;
+        for i in 0..4 {
+            for j in 0..4 {
+                res[i][j] = self[j][i];
+            }
+        }
+        res
+    }
+
+    // gauss-jordan elimination with partial pivoting. None if the matrix is singular
+    pub fn inverse(&self) -> Option<Self> {
+        let mut m = *self;
+        let mut inv = Self::IDENTITY;
+        for column in 0..4 {
+            let pivot = (column..4).max_by(|&a, &b| m[a][column].abs().total_cmp(&m[b][column].abs())).unwrap();
+            if m[pivot][column] == 0. {
+                return None;
+            }
+            m.swap_rows(column, pivot);
+            inv.swap_rows(column, pivot);
+            let scale = 1. / m[column][column];
+            m[column] *= scale;
+            inv[column] *= scale;
+            for row in 0..4 {
+                if row != column {
+                    let factor = m[row][column];
+                    m[row] = m[row] - m[column] * factor;
+                    inv[row] = inv[row] - inv[column] * factor;
+                }
+            }
+        }
+        Some(inv)
+    }
+
+    fn swap_rows(&mut self, a: usize, b: usize) {
+        let row = self[a];
+        self[a] = self[b];
+        self[b] = row;
+    }
+
+    // applies the matrix to (point, 1). Projective matrices are divided by w
+    pub fn mul_point(&self, point: Vec3) -> Vec3 {
+        let p = Vec4::new(point.x, point.y, point.z, 1.);
+        let res = Vec3::new(self.x_axis.dot(&p), self.y_axis.dot(&p), self.z_axis.dot(&p));
+        let w = self.w_axis.dot(&p);
+        if w == 1. { res } else { res / w }
+    }
+
+    // applies the matrix to (vector, 0), ignoring the translation
+    pub fn mul_vector(&self, vector: Vec3) -> Vec3 {
+        let v = Vec4::new(vector.x, vector.y, vector.z, 0.);
+        Vec3::new(self.x_axis.dot(&v), self.y_axis.dot(&v), self.z_axis.dot(&v))
+    }
+}
+
+impl ApproxEq for Mat4 {
+    fn a_eq(&self, rhs: &Self) -> bool {
+        (0..4).all(|i| self[i].a_eq(&rhs[i]))
+    }
 }
 
 impl Mul<Mat4> for Mat4 {
@@ -166,3 +261,43 @@ impl IndexMut<usize> for Mat4 {
         }
     }
 }
+
+#[cfg(test)]
+mod matrix4x4_tests {
+    use std::f32::consts::FRAC_PI_2;
+    use crate::math::{ApproxEq, Mat4, Vec3, Vec4};
+
+    #[test]
+    fn rotations() {
+        // counter clockwise by 90° maps the axes onto each other cyclically
+        assert!(Mat4::rotate_x(FRAC_PI_2).mul_vector(Vec3::Y).a_eq(&Vec3::Z));
+        assert!(Mat4::rotate_x(FRAC_PI_2).mul_vector(Vec3::Z).a_eq(&-Vec3::Y));
+        assert!(Mat4::rotate_y(FRAC_PI_2).mul_vector(Vec3::Z).a_eq(&Vec3::X));
+        assert!(Mat4::rotate_z(FRAC_PI_2).mul_vector(Vec3::X).a_eq(&Vec3::Y));
+        assert!(Mat4::rotate_z(FRAC_PI_2).mul_vector(Vec3::Z).a_eq(&Vec3::Z));
+        assert!(Mat4::rotate(Vec3::new(1., 1., 1.), 2. * std::f32::consts::PI / 3.).mul_vector(Vec3::X).a_eq(&Vec3::Y));
+    }
+
+    #[test]
+    fn inverse() {
+        let m = Mat4::new(
+            Vec4::new(2., 0., 1., 3.),
+            Vec4::new(0., 0., 4., -1.),
+            Vec4::new(1., 5., 0., 0.),
+            Vec4::new(0., 0., 0., 1.),
+        );
+        let inv = m.inverse().unwrap();
+        assert!((m * inv).a_eq(&Mat4::IDENTITY));
+        assert!((inv * m).a_eq(&Mat4::IDENTITY));
+        assert!(Mat4::ZERO.inverse().is_none());
+        assert_eq!(m.transpose().transpose(), m);
+        assert_eq!(m.transpose()[0], Vec4::new(2., 0., 1., 0.));
+    }
+
+    #[test]
+    fn points_and_vectors() {
+        let m = Mat4::translate(Vec3::new(1., 2., 3.)) * Mat4::scale(Vec3::from(2.));
+        assert_eq!(m.mul_point(Vec3::new(1., 1., 1.)), Vec3::new(3., 4., 5.));
+        assert_eq!(m.mul_vector(Vec3::new(1., 1., 1.)), Vec3::new(2., 2., 2.));
+    }
+}
@@ -4,11 +4,16 @@ mod vec4;
 mod macros;
 mod vec;
 mod matrix4x4;
+mod frame;
+mod transform;
 
 pub use vec2::Vec2;
 pub use vec3::Vec3;
 pub use vec4::Vec4;
 pub use vec::Vector;
+pub use frame::Frame;
+pub use matrix4x4::Mat4;
+pub use transform::Transform;
 
 pub const EPSILON: f32 = 0.001;
 
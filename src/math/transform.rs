@@ -0,0 +1,294 @@
+use std::ops::Mul;
+use crate::geometry::Aabb;
+use crate::math::{ApproxEq, Mat4, Vec3, Vector};
+use crate::ray::Ray;
+
+// an invertible affine transformation. The inverse is kept alongside, since normals and rays need it all the time
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Transform {
+    matrix: Mat4,
+    inverse: Mat4,
+}
+
+impl Transform {
+    pub const IDENTITY: Self = Transform {
+        matrix: Mat4::IDENTITY,
+        inverse: Mat4::IDENTITY,
+    };
+
+    // None if the matrix can't be inverted
+    pub fn new(matrix: Mat4) -> Option<Self> {
+        Some(Transform {
+            matrix,
+            inverse: matrix.inverse()?,
+        })
+    }
+
+    pub fn translate(by: Vec3) -> Self {
+        Transform {
+            matrix: Mat4::translate(by),
+            inverse: Mat4::translate(-by),
+        }
+    }
+
+    // none of the factors may be 0
+    pub fn scale(by: Vec3) -> Self {
+        debug_assert!(by.x != 0. && by.y != 0. && by.z != 0.);
+        Transform {
+            matrix: Mat4::scale(by),
+            inverse: Mat4::scale(Vec3::ONE / by),
+        }
+    }
+
+    // angles in radians, counter clockwise when looking down the axis
+    pub fn rotate_x(by: f32) -> Self {
+        Transform {
+            matrix: Mat4::rotate_x(by),
+            inverse: Mat4::rotate_x(-by),
+        }
+    }
+
+    pub fn rotate_y(by: f32) -> Self {
+        Transform {
+            matrix: Mat4::rotate_y(by),
+            inverse: Mat4::rotate_y(-by),
+        }
+    }
+
+    pub fn rotate_z(by: f32) -> Self {
+        Transform {
+            matrix: Mat4::rotate_z(by),
+            inverse: Mat4::rotate_z(-by),
+        }
+    }
+
+    pub fn rotate(axis: Vec3, by: f32) -> Self {
+        Transform {
+            matrix: Mat4::rotate(axis, by),
+            inverse: Mat4::rotate(axis, -by),
+        }
+    }
+
+    // places an object at eye with its +z axis pointing at target
+    pub fn look_at(eye: Vec3, target: Vec3, up: Vec3) -> Self {
+        let matrix = Mat4::look_at(eye, target, up);
+        // the rotational part is orthonormal, so its inverse is the transpose
+        let mut rotation = matrix;
+        for i in 0..3 {
+            rotation[i].w = 0.;
+        }
+        Transform {
+            matrix,
+            inverse: rotation.transpose() * Mat4::translate(-eye),
+        }
+    }
+
+    pub fn matrix(&self) -> &Mat4 {
+        &self.matrix
+    }
+
+    pub fn inverse_matrix(&self) -> &Mat4 {
+        &self.inverse
+    }
+
+    pub fn inverse(&self) -> Self {
+        Transform {
+            matrix: self.inverse,
+            inverse: self.matrix,
+        }
+    }
+
+    // self is applied first, then next
+    pub fn then(&self, next: &Transform) -> Self {
+        *next * *self
+    }
+
+    pub fn point(&self, point: Vec3) -> Vec3 {
+        self.matrix.mul_point(point)
+    }
+
+    // directions are not affected by the translation
+    pub fn vector(&self, vector: Vec3) -> Vec3 {
+        self.matrix.mul_vector(vector)
+    }
+
+    // normals have to stay perpendicular to the surface, so they are transformed by the inverse transpose
+    pub fn normal(&self, normal: Vec3) -> Vec3 {
+        self.inverse.transpose().mul_vector(normal).normalized()
+    }
+
+    // distances along the transformed ray are scaled with the direction, so min and max distance still describe
+    // the same points. Transform::ray_scale() returns the factor
+    pub fn ray(&self, ray: &Ray) -> Ray {
+        let direction = self.vector(ray.direction);
+        let scale = direction.length();
+        Ray::new(self.point(ray.origin), direction, Some(ray.min_distance * scale), Some(ray.max_distance * scale))
+    }
+
+    // factor between distances along a ray with the given direction and the transformed one
+    pub fn ray_scale(&self, direction: Vec3) -> f32 {
+        self.vector(direction).length()
+    }
+
+    // bounds of the transformed box
+    pub fn aabb(&self, aabb: &Aabb) -> Aabb {
+        if aabb.is_empty() {
+            return *aabb;
+        }
+        let mut bounds = Aabb::EMPTY;
+        for corner in 0..8 {
+            let p = Vec3::new(
+                if corner & 1 == 0 { aabb.min.x } else { aabb.max.x },
+                if corner & 2 == 0 { aabb.min.y } else { aabb.max.y },
+                if corner & 4 == 0 { aabb.min.z } else { aabb.max.z },
+            );
+            bounds.grow(self.point(p));
+        }
+        bounds
+    }
+
+    // true if the linear part is a rotation with uniform scaling, which keeps spheres spheres
+    pub fn is_similarity(&self) -> bool {
+        let axes = [Vec3::X, Vec3::Y, Vec3::Z].map(|a| self.vector(a));
+        let scale = axes[0].length_squared();
+        axes.iter().all(|a| (a.length_squared() / scale).a_eq(&1.))
+            && (axes[0].dot(&axes[1]) / scale).a_eq(&0.)
+            && (axes[1].dot(&axes[2]) / scale).a_eq(&0.)
+            && (axes[0].dot(&axes[2]) / scale).a_eq(&0.)
+    }
+
+    // determinant of the linear part, the factor by which volumes are scaled
+    pub fn determinant(&self) -> f32 {
+        let [x, y, z] = [Vec3::X, Vec3::Y, Vec3::Z].map(|a| self.vector(a));
+        x.cross(&y).dot(&z)
+    }
+
+    // true if the transformation mirrors, which turns the winding order of triangles around
+    pub fn swaps_handedness(&self) -> bool {
+        self.determinant() < 0.
+    }
+
+    // true if every axis is mapped onto an axis, which keeps axis aligned boxes axis aligned
+    pub fn is_axis_aligned(&self) -> bool {
+        [Vec3::X, Vec3::Y, Vec3::Z].iter().all(|&a| {
+            let v = self.vector(a);
+            [v.x, v.y, v.z].iter().filter(|c| c.abs() > v.length() * 1e-6).count() == 1
+        })
+    }
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+// like matrices, a * b applies b first
+impl Mul<Transform> for Transform {
+    type Output = Transform;
+
+    fn mul(self, rhs: Transform) -> Self::Output {
+        Transform {
+            matrix: self.matrix * rhs.matrix,
+            inverse: rhs.inverse * self.inverse,
+        }
+    }
+}
+
+impl ApproxEq for Transform {
+    fn a_eq(&self, rhs: &Self) -> bool {
+        self.matrix.a_eq(&rhs.matrix) && self.inverse.a_eq(&rhs.inverse)
+    }
+}
+
+#[cfg(test)]
+mod transform_tests {
+    use std::f32::consts::FRAC_PI_2;
+    use crate::geometry::Aabb;
+    use crate::math::{ApproxEq, Mat4, Transform, Vec3, Vector};
+    use crate::ray::Ray;
+
+    #[test]
+    fn inverse() {
+        let t = Transform::translate(Vec3::new(1., 2., 3.))
+            * Transform::rotate(Vec3::new(1., 1., 0.), 0.7)
+            * Transform::scale(Vec3::new(2., 0.5, 3.));
+        assert!((t * t.inverse()).a_eq(&Transform::IDENTITY));
+        let general = Transform::new(*t.matrix()).unwrap();
+        assert!(general.inverse_matrix().a_eq(t.inverse_matrix()));
+        assert!(Transform::new(Mat4::scale(Vec3::new(1., 0., 1.))).is_none());
+        let p = Vec3::new(-4., 0.5, 2.);
+        assert!(t.inverse().point(t.point(p)).a_eq(&p));
+    }
+
+    #[test]
+    fn composition_order() {
+        let t = Transform::translate(Vec3::new(1., 0., 0.));
+        let r = Transform::rotate_z(FRAC_PI_2);
+        // rotating first keeps the translation intact
+        assert!(r.then(&t).point(Vec3::new(1., 0., 0.)).a_eq(&Vec3::new(1., 1., 0.)));
+        assert!(t.then(&r).point(Vec3::new(1., 0., 0.)).a_eq(&Vec3::new(0., 2., 0.)));
+        assert!(t.vector(Vec3::new(1., 0., 0.)).a_eq(&Vec3::new(1., 0., 0.)));
+    }
+
+    #[test]
+    fn rotations() {
+        assert!(Transform::rotate_x(FRAC_PI_2).vector(Vec3::Y).a_eq(&Vec3::Z));
+        assert!(Transform::rotate_y(FRAC_PI_2).vector(Vec3::Z).a_eq(&Vec3::X));
+        assert!(Transform::rotate_z(FRAC_PI_2).vector(Vec3::X).a_eq(&Vec3::Y));
+        assert!(Transform::rotate(Vec3::X, 0.3).a_eq(&Transform::rotate_x(0.3)));
+        assert!(Transform::rotate(Vec3::Y, 0.3).a_eq(&Transform::rotate_y(0.3)));
+        assert!(Transform::rotate(Vec3::Z, 0.3).a_eq(&Transform::rotate_z(0.3)));
+    }
+
+    #[test]
+    fn look_at() {
+        let t = Transform::look_at(Vec3::new(1., 2., 3.), Vec3::new(1., 2., 10.), Vec3::Y);
+        assert!(t.point(Vec3::ZERO).a_eq(&Vec3::new(1., 2., 3.)));
+        assert!(t.vector(Vec3::Z).a_eq(&Vec3::Z));
+        assert!(t.vector(Vec3::Y).a_eq(&Vec3::Y));
+        let t = Transform::look_at(Vec3::ZERO, Vec3::new(-5., 0., 0.), Vec3::new(0.2, 1., 0.));
+        assert!(t.vector(Vec3::Z).a_eq(&Vec3::new(-1., 0., 0.)));
+        assert!(t.vector(Vec3::Y).a_eq(&Vec3::Y));
+        assert!((t * t.inverse()).a_eq(&Transform::IDENTITY));
+    }
+
+    #[test]
+    fn normals() {
+        // a 45° slope gets steeper when stretched along y, so its normal turns towards x
+        let t = Transform::scale(Vec3::new(1., 2., 1.));
+        let n = t.normal(Vec3::new(1., -1., 0.));
+        let tangent = t.vector(Vec3::new(1., 1., 0.));
+        assert!(n.dot(&tangent).a_eq(&0.));
+        assert!(n.a_eq(&Vec3::new(2., -1., 0.).normalized()));
+    }
+
+    #[test]
+    fn rays() {
+        let t = Transform::translate(Vec3::new(0., 0., 5.)) * Transform::scale(Vec3::from(2.));
+        let ray = Ray::new(Vec3::new(1., 0., 0.), Vec3::new(0., 3., 0.), Some(0.5), Some(4.));
+        let transformed = t.ray(&ray);
+        assert!(transformed.origin.a_eq(&Vec3::new(2., 0., 5.)));
+        assert!(transformed.direction.a_eq(&Vec3::Y));
+        // the ends of the ray are still the same points
+        assert!(transformed.at(transformed.max_distance).a_eq(&t.point(ray.at(ray.max_distance))));
+        assert!(transformed.min_distance.a_eq(&1.));
+        assert_eq!(t.ray_scale(ray.direction), 2.);
+    }
+
+    #[test]
+    fn bounds() {
+        let aabb = Aabb::new(Vec3::new(-1., -1., -1.), Vec3::new(1., 1., 1.));
+        let b = Transform::rotate_z(std::f32::consts::FRAC_PI_4).aabb(&aabb);
+        let r = 2f32.sqrt();
+        assert!(b.min.a_eq(&Vec3::new(-r, -r, -1.)));
+        assert!(b.max.a_eq(&Vec3::new(r, r, 1.)));
+        assert!(Transform::rotate_z(FRAC_PI_2).is_axis_aligned());
+        assert!(!Transform::rotate_z(0.3).is_axis_aligned());
+        assert!(Transform::rotate_z(0.3).is_similarity());
+        assert!(!Transform::scale(Vec3::new(1., 2., 1.)).is_similarity());
+        assert!(Transform::scale(Vec3::new(1., -2., 1.)).swaps_handedness());
+        assert!(!Transform::rotate_x(2.).swaps_handedness());
+        assert!(Transform::scale(Vec3::new(1., -2., 3.)).determinant().a_eq(&-6.));
+    }
+}
@@ -0,0 +1,156 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use image::RgbImage;
+use crate::camera::Camera;
+use crate::color::Color;
+use crate::film::{Film, FilmTile};
+use crate::filters::box_filter::BoxFilter;
+use crate::filters::Filter;
+use crate::integrators::Integrator;
+use crate::math::Vec2;
+use crate::sampling::Sampler;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct Tile {
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+}
+
+pub struct Renderer {
+    pub resolution: (u32, u32),
+    // edge length of the square tiles handed out to the workers
+    pub tile_size: u32,
+    pub threads: usize,
+    // number of jittered camera rays per pixel
+    pub samples_per_pixel: u32,
+    // reconstructs the pixel values from the samples
+    pub filter: Box<dyn Filter>,
+}
+
+impl Renderer {
+    pub fn new(resolution: (u32, u32)) -> Self {
+        Renderer {
+            resolution,
+            tile_size: 32,
+            threads: thread::available_parallelism().map(|n| n.get()).unwrap_or(1),
+            samples_per_pixel: 1,
+            filter: Box::new(BoxFilter::default()),
+        }
+    }
+
+    fn tiles(&self) -> Vec<Tile> {
+        let mut tiles = Vec::new();
+        for y in (0..self.resolution.1).step_by(self.tile_size as usize) {
+            for x in (0..self.resolution.0).step_by(self.tile_size as usize) {
+                tiles.push(Tile {
+                    x,
+                    y,
+                    width: self.tile_size.min(self.resolution.0 - x),
+                    height: self.tile_size.min(self.resolution.1 - y),
+                });
+            }
+        }
+        tiles
+    }
+
+    fn render_tile<'a>(&'a self, film: &Film, tile: &Tile, camera: &dyn Camera, integrator: &dyn Integrator) -> FilmTile<'a> {
+        let mut film_tile = film.tile((tile.x, tile.y), (tile.x + tile.width, tile.y + tile.height), self.filter.as_ref());
+        let resolution = Vec2::new(self.resolution.0 as f32, self.resolution.1 as f32);
+        // samples are stratified on a grid, the ones that don't fit are placed randomly
+        let strata = (self.samples_per_pixel as f32).sqrt() as u32;
+        for y in tile.y..tile.y + tile.height {
+            for x in tile.x..tile.x + tile.width {
+                let mut sampler = Sampler::new((y * self.resolution.0 + x) as u64, 0);
+                for i in 0..self.samples_per_pixel {
+                    let jitter = sampler.next_2d();
+                    let offset = if i < strata * strata {
+                        (Vec2::new((i % strata) as f32, (i / strata) as f32) + jitter) / strata as f32
+                    } else {
+                        jitter
+                    };
+                    let position = Vec2::new(x as f32, y as f32) + offset;
+                    let color = match camera.at(position / resolution, sampler.next_2d()) {
+                        Some(ray) => integrator.li(&ray, &mut sampler),
+                        None => Color::BLACK,
+                    };
+                    film_tile.add_sample(position, color);
+                }
+            }
+        }
+        film_tile
+    }
+
+    // every pixel only depends on its own coordinates and sampler seed and the tiles are merged in a fixed order,
+    // so the result is the same for any number of threads
+    pub fn render(&self, camera: &dyn Camera, integrator: &dyn Integrator) -> RgbImage {
+        self.render_film(camera, integrator).to_image()
+    }
+
+    pub fn render_film(&self, camera: &dyn Camera, integrator: &dyn Integrator) -> Film {
+        debug_assert!(self.tile_size > 0);
+        let tiles = self.tiles();
+        let mut film = Film::new(self.resolution);
+        let next_tile = AtomicUsize::new(0);
+        let mut rendered: Vec<(usize, FilmTile)> = thread::scope(|scope| {
+            let workers: Vec<_> = (0..self.threads.max(1)).map(|_| {
+                scope.spawn(|| {
+                    let mut done = Vec::new();
+                    loop {
+                        let index = next_tile.fetch_add(1, Ordering::Relaxed);
+                        let Some(tile) = tiles.get(index) else {
+                            break;
+                        };
+                        done.push((index, self.render_tile(&film, tile, camera, integrator)));
+                    }
+                    done
+                })
+            }).collect();
+            workers.into_iter().flat_map(|w| w.join().unwrap()).collect()
+        });
+
+        rendered.sort_by_key(|(index, _)| *index);
+        for (_, film_tile) in rendered {
+            film.merge(film_tile);
+        }
+        film
+    }
+}
+
+#[cfg(test)]
+mod render_tests {
+    use crate::camera::PerspectiveCamera;
+    use crate::color::Color;
+    use crate::filters::mitchell::MitchellFilter;
+    use crate::geometry::Sphere;
+    use crate::groups::GroupContent;
+    use crate::groups::bvh_group::BvhGroup;
+    use crate::integrators::path_trace::PathTraceIntegrator;
+    use crate::lights::point::PointLight;
+    use crate::math::Vec3;
+    use crate::render::Renderer;
+    use crate::world::World;
+
+    #[test]
+    fn independent_of_thread_count() {
+        let mut world = World::new(Box::new(BvhGroup::new()));
+        world.geometry.push(GroupContent::new(Box::new(Sphere::new(Vec3::new(0., 0., 5.), 1.)), None));
+        world.geometry.push(GroupContent::new(Box::new(Sphere::new(Vec3::new(1., 1., 4.), 0.5)), None));
+        world.geometry.build();
+        world.lights.push(Box::new(PointLight::new(Vec3::new(2., 2., 0.), Color::new(20., 20., 20.))));
+        let cam = PerspectiveCamera::new(Vec3::ZERO, Vec3::Z, Vec3::Y, 1., 60.);
+        let integrator = PathTraceIntegrator::new(&world);
+
+        let mut renderer = Renderer::new((67, 45));
+        renderer.tile_size = 16;
+        renderer.samples_per_pixel = 5;
+        renderer.filter = Box::new(MitchellFilter::default());
+        renderer.threads = 1;
+        let single = renderer.render(&cam, &integrator);
+        renderer.threads = 5;
+        let multi = renderer.render(&cam, &integrator);
+        assert_eq!(single.dimensions(), (67, 45));
+        assert_eq!(single.as_raw(), multi.as_raw());
+    }
+}
@@ -0,0 +1,139 @@
+use crate::math::Vec2;
+
+// piecewise constant density over [0, 1) with one piece per function value
+#[derive(Clone, Debug, PartialEq)]
+pub struct Distribution1D {
+    func: Vec<f32>,
+    // cdf[i] is the probability of landing before piece i, the last entry is 1
+    cdf: Vec<f32>,
+    // integral of func over [0, 1)
+    integral: f32,
+}
+
+impl Distribution1D {
+    // the values must not be negative. If they are all 0, every piece is equally likely
+    pub fn new(func: &[f32]) -> Self {
+        assert!(!func.is_empty(), "a distribution needs at least one value");
+        let n = func.len() as f32;
+        let mut cdf = Vec::with_capacity(func.len() + 1);
+        cdf.push(0.);
+        for (i, f) in func.iter().enumerate() {
+            cdf.push(cdf[i] + f / n);
+        }
+        let integral = cdf[func.len()];
+        if integral > 0. {
+            cdf.iter_mut().for_each(|c| *c /= integral);
+        } else {
+            cdf.iter_mut().enumerate().for_each(|(i, c)| *c = i as f32 / n);
+        }
+        Distribution1D {
+            func: func.to_vec(),
+            cdf,
+            integral,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.func.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.func.is_empty()
+    }
+
+    pub fn integral(&self) -> f32 {
+        self.integral
+    }
+
+    // returns the sampled position, its density and the index of the piece it is in
+    pub fn sample(&self, u: f32) -> (f32, f32, usize) {
+        // last piece whose start is not beyond u, pieces without probability are never picked
+        let index = (self.cdf.partition_point(|&c| c <= u) - 1).min(self.len() - 1);
+        let width = self.cdf[index + 1] - self.cdf[index];
+        let offset = if width > 0. { (u - self.cdf[index]) / width } else { 0. };
+        let x = ((index as f32 + offset.clamp(0., 1.)) / self.len() as f32).min(1. - f32::EPSILON);
+        (x, self.pdf(x), index)
+    }
+
+    pub fn pdf(&self, x: f32) -> f32 {
+        let index = ((x * self.len() as f32) as usize).min(self.len() - 1);
+        (self.cdf[index + 1] - self.cdf[index]) * self.len() as f32
+    }
+}
+
+// piecewise constant density over [0, 1)² given as rows of values. Picks a row first and then a position within it
+#[derive(Clone, Debug, PartialEq)]
+pub struct Distribution2D {
+    rows: Vec<Distribution1D>,
+    marginal: Distribution1D,
+}
+
+impl Distribution2D {
+    // values are stored row by row, all rows have the given width
+    pub fn new(values: &[f32], width: usize) -> Self {
+        assert!(width > 0 && values.len().is_multiple_of(width), "the values have to fill whole rows");
+        let rows: Vec<Distribution1D> = values.chunks(width).map(Distribution1D::new).collect();
+        let marginal = Distribution1D::new(&rows.iter().map(|r| r.integral()).collect::<Vec<f32>>());
+        Distribution2D {
+            rows,
+            marginal,
+        }
+    }
+
+    // returns the sampled position, x along the rows and y across them, and its density
+    pub fn sample(&self, u: Vec2) -> (Vec2, f32) {
+        let (y, pdf_y, row) = self.marginal.sample(u.y);
+        let (x, pdf_x, _) = self.rows[row].sample(u.x);
+        (Vec2::new(x, y), pdf_x * pdf_y)
+    }
+
+    pub fn pdf(&self, p: Vec2) -> f32 {
+        let row = ((p.y * self.rows.len() as f32) as usize).min(self.rows.len() - 1);
+        self.marginal.pdf(p.y) * self.rows[row].pdf(p.x)
+    }
+}
+
+#[cfg(test)]
+mod distribution_tests {
+    use crate::math::{ApproxEq, Vec2};
+    use crate::sampling::Sampler;
+    use crate::sampling::distribution::{Distribution1D, Distribution2D};
+
+    #[test]
+    fn one_dimension() {
+        let d = Distribution1D::new(&[1., 0., 3.]);
+        assert!(d.integral().a_eq(&(4. / 3.)));
+        assert!(d.pdf(0.1).a_eq(&0.75));
+        assert_eq!(d.pdf(0.5), 0.);
+        assert!(d.pdf(0.9).a_eq(&2.25));
+        let mut sampler = Sampler::new(0, 0);
+        let mut counts = [0; 3];
+        for _ in 0..10000 {
+            let (x, pdf, index) = d.sample(sampler.next_f32());
+            assert_eq!(index, (x * 3.) as usize);
+            assert_eq!(pdf, d.pdf(x));
+            counts[index] += 1;
+        }
+        assert_eq!(counts[1], 0);
+        assert!((counts[2] as f32 / 10000. - 0.75).abs() < 0.02);
+        // without any weight every piece is equally likely
+        assert!(Distribution1D::new(&[0., 0.]).pdf(0.7).a_eq(&1.));
+    }
+
+    #[test]
+    fn two_dimensions() {
+        let d = Distribution2D::new(&[1., 2., 0., 0., 3., 2.], 2);
+        let mut sampler = Sampler::new(0, 0);
+        // the density integrates to 1
+        let mut sum = 0.;
+        for _ in 0..10000 {
+            let u = sampler.next_2d();
+            sum += d.pdf(u);
+            let (p, pdf) = d.sample(u);
+            assert!(pdf.a_eq(&d.pdf(p)));
+            assert!(p.y < 1. / 3. || p.y >= 2. / 3.);
+        }
+        assert!((sum / 10000. - 1.).abs() < 0.02);
+        assert!(d.pdf(Vec2::new(0.75, 0.9)).a_eq(&(2. / 8. * 3. * 2.)));
+    }
+}
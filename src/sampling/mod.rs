@@ -0,0 +1,74 @@
+use crate::math::Vec2;
+
+pub mod warp;
+pub mod distribution;
+
+const PCG_MULTIPLIER: u64 = 6364136223846793005;
+
+// PCG32 random number generator (O'Neill 2014)
+// every pixel gets its own seeded instance, so images don't depend on the order pixels are rendered in
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sampler {
+    state: u64,
+    increment: u64,
+}
+
+impl Sampler {
+    pub fn new(seed: u64, stream: u64) -> Self {
+        let mut sampler = Sampler {
+            state: 0,
+            increment: (stream << 1) | 1,
+        };
+        sampler.next_u32();
+        sampler.state = sampler.state.wrapping_add(seed);
+        sampler.next_u32();
+        sampler
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        let old = self.state;
+        self.state = old.wrapping_mul(PCG_MULTIPLIER).wrapping_add(self.increment);
+        let xor_shifted = (((old >> 18) ^ old) >> 27) as u32;
+        xor_shifted.rotate_right((old >> 59) as u32)
+    }
+
+    // uniform in [0, 1)
+    pub fn next_f32(&mut self) -> f32 {
+        // only 24 bits fit into the mantissa, more would round up to 1 occasionally
+        (self.next_u32() >> 8) as f32 / (1u32 << 24) as f32
+    }
+
+    // uniform in [0, 1)²
+    pub fn next_2d(&mut self) -> Vec2 {
+        Vec2::new(self.next_f32(), self.next_f32())
+    }
+}
+
+#[cfg(test)]
+mod sampler_tests {
+    use crate::sampling::Sampler;
+
+    #[test]
+    fn deterministic() {
+        let mut s1 = Sampler::new(12, 3);
+        let mut s2 = Sampler::new(12, 3);
+        let mut s3 = Sampler::new(12, 4);
+        let a: Vec<u32> = (0..10).map(|_| s1.next_u32()).collect();
+        let b: Vec<u32> = (0..10).map(|_| s2.next_u32()).collect();
+        let c: Vec<u32> = (0..10).map(|_| s3.next_u32()).collect();
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+    }
+
+    #[test]
+    fn range() {
+        let mut s = Sampler::new(0, 0);
+        let mut sum = 0.;
+        for _ in 0..10000 {
+            let x = s.next_f32();
+            assert!((0. ..1.).contains(&x));
+            sum += x;
+        }
+        assert!((sum / 10000. - 0.5).abs() < 0.02);
+    }
+}
@@ -0,0 +1,102 @@
+use std::f32::consts::{FRAC_PI_2, FRAC_PI_4, PI};
+use crate::math::{Vec2, Vec3};
+
+// maps the unit square onto the unit disk, keeping neighbouring samples close (Shirley & Chiu 1997)
+pub fn concentric_disk(u: Vec2) -> Vec2 {
+    let offset = u * 2. - 1.;
+    if offset.x == 0. && offset.y == 0. {
+        return Vec2::ZERO;
+    }
+    let (r, theta) = if offset.x.abs() > offset.y.abs() {
+        (offset.x, FRAC_PI_4 * (offset.y / offset.x))
+    } else {
+        (offset.y, FRAC_PI_2 - FRAC_PI_4 * (offset.x / offset.y))
+    };
+    Vec2::new(theta.cos(), theta.sin()) * r
+}
+
+// directions around +z with a density proportional to the cosine of the angle to z
+pub fn cosine_hemisphere(u: Vec2) -> Vec3 {
+    let d = concentric_disk(u);
+    let z = (1. - d.x * d.x - d.y * d.y).max(0.).sqrt();
+    Vec3::new(d.x, d.y, z)
+}
+
+pub fn cosine_hemisphere_pdf(cos_theta: f32) -> f32 {
+    cos_theta.max(0.) / PI
+}
+
+pub fn uniform_sphere(u: Vec2) -> Vec3 {
+    let z = 1. - 2. * u.x;
+    let r = (1. - z * z).max(0.).sqrt();
+    let phi = 2. * PI * u.y;
+    Vec3::new(r * phi.cos(), r * phi.sin(), z)
+}
+
+pub fn uniform_sphere_pdf() -> f32 {
+    1. / (4. * PI)
+}
+
+// directions around +z within the angle whose cosine is cos_max
+pub fn uniform_cone(u: Vec2, cos_max: f32) -> Vec3 {
+    let z = 1. - u.x * (1. - cos_max);
+    let r = (1. - z * z).max(0.).sqrt();
+    let phi = 2. * PI * u.y;
+    Vec3::new(r * phi.cos(), r * phi.sin(), z)
+}
+
+pub fn uniform_cone_pdf(cos_max: f32) -> f32 {
+    1. / (2. * PI * (1. - cos_max))
+}
+
+// uniformly distributed barycentric coordinates of the second and third vertex
+pub fn uniform_triangle(u: Vec2) -> Vec2 {
+    let s = u.x.sqrt();
+    Vec2::new(s * (1. - u.y), s * u.y)
+}
+
+#[cfg(test)]
+mod warp_tests {
+    use crate::math::{EPSILON, Vec2, Vector};
+    use crate::sampling::Sampler;
+    use crate::sampling::warp::{concentric_disk, cosine_hemisphere, uniform_cone, uniform_sphere, uniform_triangle};
+
+    #[test]
+    fn disk() {
+        let mut s = Sampler::new(1, 1);
+        for _ in 0..1000 {
+            assert!(concentric_disk(s.next_2d()).length() <= 1. + EPSILON);
+        }
+        assert_eq!(concentric_disk(Vec2::new(0.5, 0.5)), Vec2::ZERO);
+    }
+
+    #[test]
+    fn hemisphere() {
+        let mut s = Sampler::new(2, 1);
+        for _ in 0..1000 {
+            let d = cosine_hemisphere(s.next_2d());
+            assert!(d.z >= 0.);
+            assert!((d.length() - 1.).abs() < EPSILON);
+        }
+    }
+
+    #[test]
+    fn sphere() {
+        let mut s = Sampler::new(3, 1);
+        for _ in 0..1000 {
+            assert!((uniform_sphere(s.next_2d()).length() - 1.).abs() < EPSILON);
+        }
+    }
+
+    #[test]
+    fn cone_and_triangle() {
+        let mut s = Sampler::new(4, 1);
+        for _ in 0..1000 {
+            let d = uniform_cone(s.next_2d(), 0.9);
+            assert!(d.z >= 0.9 - EPSILON);
+            assert!((d.length() - 1.).abs() < EPSILON);
+            let b = uniform_triangle(s.next_2d());
+            assert!(b.x >= 0. && b.y >= 0. && b.x + b.y <= 1. + EPSILON);
+        }
+    }
+}
@@ -0,0 +1,184 @@
+use std::fs::File;
+use std::io::{BufReader, Error, ErrorKind};
+use std::path::Path;
+use image::DynamicImage;
+use image::codecs::hdr::HdrDecoder;
+use crate::color::Color;
+use crate::lights::environment::EnvironmentLight;
+use crate::math::Transform;
+use crate::scene::SceneError;
+
+// floating point image, rows from top to bottom
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct HdrImage {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<Color>,
+}
+
+impl HdrImage {
+    // Radiance .hdr, PFM, and anything else the image crate can read
+    pub fn load(path: &Path) -> Result<HdrImage, SceneError> {
+        let io_error = |error| SceneError::Io {
+            path: path.display().to_string(),
+            error,
+        };
+        let image_error = |e| match e {
+            image::ImageError::IoError(e) => io_error(e),
+            e => io_error(Error::new(ErrorKind::InvalidData, e.to_string())),
+        };
+        let extension = path.extension().map(|e| e.to_ascii_lowercase());
+        match extension.as_ref().and_then(|e| e.to_str()) {
+            Some("pfm") => {
+                let bytes = std::fs::read(path).map_err(io_error)?;
+                return parse_pfm(&bytes).map_err(|message| io_error(Error::new(ErrorKind::InvalidData, message)));
+            }
+            // going through DynamicImage would tone map it to 8 bits
+            Some("hdr") => {
+                let file = BufReader::new(File::open(path).map_err(io_error)?);
+                let decoder = HdrDecoder::new(file).map_err(image_error)?;
+                let (width, height) = (decoder.metadata().width as usize, decoder.metadata().height as usize);
+                let pixels = decoder.read_image_hdr().map_err(image_error)?;
+                return Ok(HdrImage {
+                    width,
+                    height,
+                    pixels: pixels.iter().map(|p| Color::new(p[0], p[1], p[2])).collect(),
+                });
+            }
+            _ => {}
+        }
+        let image = image::open(path).map_err(image_error)?;
+        // integer formats like png and jpeg store sRGB encoded values, floating point ones are linear already
+        let encoded = !matches!(image, DynamicImage::ImageRgb32F(_) | DynamicImage::ImageRgba32F(_));
+        let image = image.into_rgb32f();
+        let decode = |v: f32| if encoded { srgb_to_linear(v) } else { v };
+        Ok(HdrImage {
+            width: image.width() as usize,
+            height: image.height() as usize,
+            pixels: image.pixels().map(|p| Color::new(decode(p[0]), decode(p[1]), decode(p[2]))).collect(),
+        })
+    }
+
+    pub fn to_environment_light(&self, transform: Transform, intensity: f32) -> EnvironmentLight {
+        EnvironmentLight::new(self.width, self.height, self.pixels.clone(), transform, intensity)
+    }
+}
+
+// inverse of the sRGB transfer curve, for values in [0, 1]
+fn srgb_to_linear(v: f32) -> f32 {
+    if v <= 0.04045 {
+        v / 12.92
+    } else {
+        ((v + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+// portable float map: "PF" (color) or "Pf" (gray), the size and a scale whose sign gives the byte order, followed by
+// the rows from bottom to top
+pub fn parse_pfm(bytes: &[u8]) -> Result<HdrImage, String> {
+    // the header are three whitespace separated tokens after the magic, followed by a single whitespace character
+    let mut tokens = Vec::with_capacity(4);
+    let mut position = 0;
+    while tokens.len() < 4 {
+        while position < bytes.len() && bytes[position].is_ascii_whitespace() {
+            position += 1;
+        }
+        let start = position;
+        while position < bytes.len() && !bytes[position].is_ascii_whitespace() {
+            position += 1;
+        }
+        if start == position {
+            return Err("the header is incomplete".to_string());
+        }
+        tokens.push(String::from_utf8_lossy(&bytes[start..position]).into_owned());
+    }
+    let data = &bytes[(position + 1).min(bytes.len())..];
+    let channels = match tokens[0].as_str() {
+        "PF" => 3,
+        "Pf" => 1,
+        magic => return Err(format!("'{}' is not a PFM file", magic)),
+    };
+    let number = |token: &str| token.parse::<f32>().map_err(|_| format!("expected a number, found '{}'", token));
+    let (width, height, scale) = (number(&tokens[1])?, number(&tokens[2])?, number(&tokens[3])?);
+    if width < 1. || height < 1. || width.fract() != 0. || height.fract() != 0. || scale == 0. {
+        return Err("invalid image size or scale".to_string());
+    }
+    let (width, height) = (width as usize, height as usize);
+    // the header can claim any size, so it must not overflow before it's compared with the data
+    let expected = width.checked_mul(height)
+        .and_then(|n| n.checked_mul(channels * 4))
+        .ok_or_else(|| "invalid image size".to_string())?;
+    if data.len() < expected {
+        return Err(format!("expected {} bytes of pixel data, found {}", expected, data.len()));
+    }
+    let values: Vec<f32> = data[..expected].chunks_exact(4).map(|b| {
+        let b = [b[0], b[1], b[2], b[3]];
+        if scale < 0. { f32::from_le_bytes(b) } else { f32::from_be_bytes(b) }
+    }).collect();
+    let mut pixels = Vec::with_capacity(width * height);
+    for row in values.chunks_exact(width * channels).rev() {
+        pixels.extend(row.chunks_exact(channels).map(|c| match c {
+            [r, g, b] => Color::new(*r, *g, *b),
+            _ => Color::new(c[0], c[0], c[0]),
+        }));
+    }
+    Ok(HdrImage {
+        width,
+        height,
+        pixels,
+    })
+}
+
+#[cfg(test)]
+mod hdr_tests {
+    use crate::color::Color;
+    use crate::scene::TempDir;
+    use crate::scene::hdr::{parse_pfm, HdrImage};
+
+    #[test]
+    fn pfm() {
+        let mut bytes = b"PF\n2 1\n-1.0\n".to_vec();
+        for v in [1f32, 2., 3., 4., 5., 6.] {
+            bytes.extend(v.to_le_bytes());
+        }
+        let image = parse_pfm(&bytes).unwrap();
+        assert_eq!((image.width, image.height), (2, 1));
+        assert_eq!(image.pixels, vec![Color::new(1., 2., 3.), Color::new(4., 5., 6.)]);
+        // big endian gray, stored bottom row first
+        let mut bytes = b"Pf 1 2 1\n".to_vec();
+        for v in [0.25f32, 0.5] {
+            bytes.extend(v.to_be_bytes());
+        }
+        let image = parse_pfm(&bytes).unwrap();
+        assert_eq!(image.pixels, vec![Color::new(0.5, 0.5, 0.5), Color::new(0.25, 0.25, 0.25)]);
+        assert_eq!(parse_pfm(b"P6 1 1 1\n").unwrap_err(), "'P6' is not a PFM file");
+        assert_eq!(parse_pfm(b"PF 1 1 -1\n\0\0").unwrap_err(), "expected 12 bytes of pixel data, found 2");
+        assert_eq!(parse_pfm(b"PF 18446744073709551615 4 -1\n").unwrap_err(), "invalid image size");
+    }
+
+    #[test]
+    fn radiance_hdr() {
+        let dir = TempDir::new("hdr_radiance");
+        let path = dir.join("test.hdr");
+        let pixels = vec![image::Rgb([1., 0.5, 0.25]), image::Rgb([8., 0., 0.])];
+        let file = std::fs::File::create(&path).unwrap();
+        image::codecs::hdr::HdrEncoder::new(file).encode(&pixels, 2, 1).unwrap();
+        let image = HdrImage::load(&path).unwrap();
+        assert_eq!((image.width, image.height), (2, 1));
+        assert_eq!(image.pixels, vec![Color::new(1., 0.5, 0.25), Color::new(8., 0., 0.)]);
+        assert!(HdrImage::load(&dir.join("missing.hdr")).unwrap_err().to_string().starts_with("could not read"));
+    }
+
+    #[test]
+    fn srgb_png() {
+        let dir = TempDir::new("hdr_srgb");
+        let path = dir.join("test.png");
+        image::GrayImage::from_raw(3, 1, vec![0, 188, 255]).unwrap().save(&path).unwrap();
+        let image = HdrImage::load(&path).unwrap();
+        // 188 is about half the light of 255
+        let values: Vec<f32> = image.pixels.iter().map(|c| c.r).collect();
+        assert_eq!(values[0], 0.);
+        assert!((values[1] - 0.5).abs() < 0.01, "{:?}", values);
+        assert!((values[2] - 1.).abs() < 1e-6);
+    }
+}
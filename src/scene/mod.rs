@@ -0,0 +1,114 @@
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+use crate::camera::Camera;
+use crate::integrators::Integrator;
+use crate::integrators::path_trace::PathTraceIntegrator;
+use crate::integrators::ray_trace::RayTraceIntegrator;
+use crate::render::Renderer;
+use crate::world::World;
+
+pub mod obj;
+pub mod hdr;
+mod tokenizer;
+mod parser;
+
+#[derive(Debug)]
+pub enum SceneError {
+    Io {
+        path: String,
+        error: std::io::Error,
+    },
+    // line and column are 1-based
+    Syntax {
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    // an error in a file referenced by the scene, like a mesh
+    InFile {
+        path: String,
+        error: Box<SceneError>,
+    },
+}
+
+impl Display for SceneError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SceneError::Io { path, error } => write!(f, "could not read {}: {}", path, error),
+            SceneError::Syntax { line, column, message } => write!(f, "line {}, column {}: {}", line, column, message),
+            SceneError::InFile { path, error } => write!(f, "{}: {}", path, error),
+        }
+    }
+}
+
+impl std::error::Error for SceneError {}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum IntegratorSettings {
+    RayTrace,
+    PathTrace {
+        max_depth: u32,
+        rr_depth: u32,
+    },
+}
+
+// everything needed to render an image
+pub struct Scene {
+    pub world: World,
+    pub camera: Box<dyn Camera>,
+    pub renderer: Renderer,
+    pub integrator: IntegratorSettings,
+    // file name of the rendered image, if the scene specifies one
+    pub output: Option<String>,
+}
+
+impl Scene {
+    pub fn load(path: impl AsRef<Path>) -> Result<Scene, SceneError> {
+        let path = path.as_ref();
+        let source = std::fs::read_to_string(path).map_err(|error| SceneError::Io {
+            path: path.display().to_string(),
+            error,
+        })?;
+        // files referenced by the scene are relative to it
+        Self::parse(&source, path.parent().unwrap_or(Path::new("")))
+    }
+
+    pub fn parse(source: &str, base_path: &Path) -> Result<Scene, SceneError> {
+        parser::Parser::new(tokenizer::tokenize(source)?, base_path).parse()
+    }
+
+    pub fn integrator(&self) -> Box<dyn Integrator + '_> {
+        match self.integrator {
+            IntegratorSettings::RayTrace => Box::new(RayTraceIntegrator { world: &self.world }),
+            IntegratorSettings::PathTrace { max_depth, rr_depth } => Box::new(PathTraceIntegrator {
+                world: &self.world,
+                max_depth,
+                rr_depth,
+            }),
+        }
+    }
+}
+
+// a directory for the files a test reads, unique to the test run and removed again when it's dropped
+#[cfg(test)]
+pub(crate) struct TempDir(std::path::PathBuf);
+
+#[cfg(test)]
+impl TempDir {
+    pub(crate) fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("rayst_{}_{}", name, std::process::id()));
+        std::fs::create_dir_all(&path).unwrap();
+        TempDir(path)
+    }
+
+    pub(crate) fn join(&self, file: &str) -> std::path::PathBuf {
+        self.0.join(file)
+    }
+}
+
+#[cfg(test)]
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
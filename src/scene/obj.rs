@@ -0,0 +1,490 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+use crate::color::Color;
+use crate::geometry::TriangleMesh;
+use crate::materials::Material;
+use crate::materials::dielectric::Dielectric;
+use crate::materials::emissive::Emissive;
+use crate::materials::lambertian::Lambertian;
+use crate::materials::conductor::RoughConductor;
+use crate::materials::mirror::Mirror;
+use crate::materials::rough_dielectric::RoughDielectric;
+use crate::math::{Vec2, Vec3};
+use crate::scene::SceneError;
+
+// triangles sharing their vertices. All faces of a mesh belong to the same object and use the same material
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ObjMesh {
+    // name of the object or group, empty if the file doesn't name it
+    pub name: String,
+    // index into Obj::materials, None if no material was set with usemtl
+    pub material: Option<usize>,
+    pub positions: Vec<Vec3>,
+    // one per position, or empty if not every vertex of the mesh has one
+    pub normals: Vec<Vec3>,
+    pub uvs: Vec<Vec2>,
+    // counter clockwise vertex indices
+    pub triangles: Vec<[u32; 3]>,
+}
+
+impl ObjMesh {
+    pub fn to_triangle_mesh(&self) -> TriangleMesh {
+        // obj faces are counter clockwise, triangles define their outside the other way around
+        let indices = self.triangles.iter().map(|&[a, b, c]| [a, c, b]).collect();
+        TriangleMesh::new(self.positions.clone(), self.normals.clone(), self.uvs.clone(), indices)
+    }
+}
+
+// the subset of the MTL format that maps onto our materials
+#[derive(Clone, Debug, PartialEq)]
+pub struct ObjMaterial {
+    pub name: String,
+    // Kd, Ks and Ke
+    pub diffuse: Color,
+    pub specular: Color,
+    pub emission: Color,
+    // Ns, the phong exponent
+    pub shininess: Option<f32>,
+    // Ni
+    pub ior: f32,
+    // d, or 1 - Tr
+    pub opacity: f32,
+    pub illum: u32,
+    // Pr and Pm from the PBR extension
+    pub roughness: Option<f32>,
+    pub metallic: f32,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Obj {
+    pub meshes: Vec<ObjMesh>,
+    pub materials: Vec<ObjMaterial>,
+}
+
+impl Default for ObjMaterial {
+    fn default() -> Self {
+        ObjMaterial {
+            name: String::new(),
+            diffuse: Color::new(0.8, 0.8, 0.8),
+            specular: Color::BLACK,
+            emission: Color::BLACK,
+            shininess: None,
+            ior: 1.5,
+            opacity: 1.,
+            illum: 2,
+            roughness: None,
+            metallic: 0.,
+        }
+    }
+}
+
+// below this roughness glass and metal look like perfectly smooth ones. Reached by phong exponents above about 250
+const SMOOTH_ROUGHNESS: f32 = 0.3;
+
+impl ObjMaterial {
+    // microfacet roughness, either given directly or converted from the phong exponent. Smooth if neither is set
+    pub fn roughness(&self) -> f32 {
+        match (self.roughness, self.shininess) {
+            (Some(r), _) => r.clamp(0., 1.),
+            // phong exponent n corresponds to a beckmann alpha of sqrt(2 / (n + 2)), and alpha = roughness²
+            (None, Some(n)) => (2. / (n.max(0.) + 2.)).powf(0.25),
+            (None, None) => 0.,
+        }
+    }
+
+    pub fn to_material(&self) -> Arc<dyn Material> {
+        // illum 4, 6 and 7 are glass, 3 and 5 are reflective
+        let transparent = self.opacity < 1. || matches!(self.illum, 4 | 6 | 7);
+        let reflective = self.metallic > 0.5 || matches!(self.illum, 3 | 5);
+        // emitters don't reflect, whatever else is set
+        if self.emission.max_component() > 0. {
+            Arc::new(Emissive::new(self.emission))
+        } else if transparent {
+            if self.roughness() < SMOOTH_ROUGHNESS {
+                Arc::new(Dielectric::new(self.ior))
+            } else {
+                Arc::new(RoughDielectric::new(self.ior, self.roughness()))
+            }
+        } else if reflective {
+            // metallic workflows store the reflectance in the diffuse color
+            let color = if self.metallic > 0.5 { self.diffuse } else { self.specular };
+            if self.roughness() < SMOOTH_ROUGHNESS {
+                Arc::new(Mirror::new(color))
+            } else {
+                // a conductor with eta 1 reflects k² / (4 + k²) at normal incidence
+                let k = |r: f32| 2. * (r.min(0.999) / (1. - r.min(0.999))).sqrt();
+                let k = Color::new(k(color.r), k(color.g), k(color.b));
+                Arc::new(RoughConductor::new(Color::WHITE, k, self.roughness()))
+            }
+        } else {
+            Arc::new(Lambertian::new(self.diffuse))
+        }
+    }
+}
+
+// splits a line into words and their 1-based columns
+fn words(line: &str) -> Vec<(usize, &str)> {
+    let mut words = Vec::new();
+    let mut start = None;
+    for (i, c) in line.char_indices().chain(std::iter::once((line.len(), ' '))) {
+        match (start, c.is_whitespace()) {
+            (None, false) => start = Some(i),
+            (Some(s), true) => {
+                words.push((line[..s].chars().count() + 1, &line[s..i]));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    words
+}
+
+fn syntax_error(line: usize, column: usize, message: impl Into<String>) -> SceneError {
+    SceneError::Syntax {
+        line,
+        column,
+        message: message.into(),
+    }
+}
+
+// reads the arguments of a statement as numbers. Missing optional arguments are left at the default
+fn numbers<const N: usize>(line: usize, words: &[(usize, &str)], required: usize, default: [f32; N]) -> Result<[f32; N], SceneError> {
+    let (keyword_column, keyword) = words[0];
+    let arguments = &words[1..];
+    if arguments.len() < required || arguments.len() > N {
+        let column = arguments.get(N).map(|w| w.0).unwrap_or(keyword_column);
+        return Err(syntax_error(line, column, format!("'{}' expects {} to {} numbers, found {}", keyword, required, N, arguments.len())));
+    }
+    let mut values = default;
+    for (value, &(column, word)) in values.iter_mut().zip(arguments) {
+        *value = word.parse::<f32>().ok().filter(|v| v.is_finite())
+            .ok_or_else(|| syntax_error(line, column, format!("expected a number, found '{}'", word)))?;
+    }
+    Ok(values)
+}
+
+fn read(path: &Path) -> Result<String, SceneError> {
+    std::fs::read_to_string(path).map_err(|error| SceneError::Io {
+        path: path.display().to_string(),
+        error,
+    })
+}
+
+// attaches the file name to errors in files referenced by another one
+fn in_file(path: &Path) -> impl FnOnce(SceneError) -> SceneError + '_ {
+    move |error| match error {
+        SceneError::Io { .. } | SceneError::InFile { .. } => error,
+        _ => SceneError::InFile {
+            path: path.display().to_string(),
+            error: Box::new(error),
+        },
+    }
+}
+
+pub fn parse_mtl(source: &str) -> Result<Vec<ObjMaterial>, SceneError> {
+    let mut materials: Vec<ObjMaterial> = Vec::new();
+    for (index, line) in source.lines().enumerate() {
+        let line_number = index + 1;
+        let line = line.split('#').next().unwrap();
+        let words = words(line);
+        let Some(&(column, keyword)) = words.first() else {
+            continue;
+        };
+        if keyword == "newmtl" {
+            let name = line[line.find("newmtl").unwrap() + 6..].trim();
+            if name.is_empty() {
+                return Err(syntax_error(line_number, column, "'newmtl' expects a name"));
+            }
+            materials.push(ObjMaterial {
+                name: name.to_string(),
+                ..ObjMaterial::default()
+            });
+            continue;
+        }
+        let Some(material) = materials.last_mut() else {
+            return Err(syntax_error(line_number, column, format!("'{}' before the first 'newmtl'", keyword)));
+        };
+        // a single value is a shade of gray
+        let color = |words: &[(usize, &str)]| {
+            numbers(line_number, words, 1, [f32::NAN; 3]).map(|[r, g, b]| {
+                if g.is_nan() { Color::new(r, r, r) } else { Color::new(r, g, b) }
+            })
+        };
+        match keyword {
+            "Kd" => material.diffuse = color(&words)?,
+            "Ks" => material.specular = color(&words)?,
+            "Ke" => material.emission = color(&words)?,
+            "Ns" => material.shininess = Some(numbers(line_number, &words, 1, [0.])?[0]),
+            "Ni" => material.ior = numbers(line_number, &words, 1, [0.])?[0],
+            "d" => material.opacity = numbers(line_number, &words, 1, [0.])?[0],
+            "Tr" => material.opacity = 1. - numbers(line_number, &words, 1, [0.])?[0],
+            "Pr" => material.roughness = Some(numbers(line_number, &words, 1, [0.])?[0]),
+            "Pm" => material.metallic = numbers(line_number, &words, 1, [0.])?[0],
+            "illum" => {
+                let (column, word) = words.get(1).copied().unwrap_or((column, ""));
+                material.illum = word.parse().map_err(|_| syntax_error(line_number, column, format!("expected an illumination model, found '{}'", word)))?;
+            }
+            // textures, Ka and the rest have no equivalent
+            _ => {}
+        }
+    }
+    Ok(materials)
+}
+
+// collects the faces of one mesh and deduplicates their vertices
+struct MeshBuilder {
+    mesh: ObjMesh,
+    // (position, uv, normal) indices of the file to the index in the mesh
+    vertices: HashMap<(usize, Option<usize>, Option<usize>), u32>,
+    complete_normals: bool,
+    complete_uvs: bool,
+}
+
+impl MeshBuilder {
+    fn new(name: &str, material: Option<usize>) -> Self {
+        MeshBuilder {
+            mesh: ObjMesh {
+                name: name.to_string(),
+                material,
+                ..ObjMesh::default()
+            },
+            vertices: HashMap::new(),
+            complete_normals: true,
+            complete_uvs: true,
+        }
+    }
+
+    fn vertex(&mut self, key: (usize, Option<usize>, Option<usize>), positions: &[Vec3], uvs: &[Vec2], normals: &[Vec3]) -> u32 {
+        if let Some(&index) = self.vertices.get(&key) {
+            return index;
+        }
+        let index = self.mesh.positions.len() as u32;
+        self.mesh.positions.push(positions[key.0]);
+        match key.1 {
+            Some(uv) => self.mesh.uvs.push(uvs[uv]),
+            None => self.complete_uvs = false,
+        }
+        match key.2 {
+            Some(n) => self.mesh.normals.push(normals[n]),
+            None => self.complete_normals = false,
+        }
+        self.vertices.insert(key, index);
+        index
+    }
+
+    fn finish(mut self) -> Option<ObjMesh> {
+        if self.mesh.triangles.is_empty() {
+            return None;
+        }
+        if !self.complete_normals {
+            self.mesh.normals.clear();
+        }
+        if !self.complete_uvs {
+            self.mesh.uvs.clear();
+        }
+        Some(self.mesh)
+    }
+}
+
+impl Obj {
+    pub fn load(path: impl AsRef<Path>) -> Result<Obj, SceneError> {
+        let path = path.as_ref();
+        let source = read(path)?;
+        // material libraries are relative to the obj file
+        Self::parse(&source, path.parent().unwrap_or(Path::new(""))).map_err(in_file(path))
+    }
+
+    pub fn parse(source: &str, base_path: &Path) -> Result<Obj, SceneError> {
+        let mut obj = Obj::default();
+        let mut positions: Vec<Vec3> = Vec::new();
+        let mut normals: Vec<Vec3> = Vec::new();
+        let mut uvs: Vec<Vec2> = Vec::new();
+        let mut name = String::new();
+        let mut material = None;
+        let mut current = MeshBuilder::new(&name, material);
+
+        for (index, line) in source.lines().enumerate() {
+            let line_number = index + 1;
+            let line = line.split('#').next().unwrap();
+            let words = words(line);
+            let Some(&(column, keyword)) = words.first() else {
+                continue;
+            };
+            match keyword {
+                "v" => {
+                    let [x, y, z, _] = numbers(line_number, &words, 3, [0., 0., 0., 1.])?;
+                    positions.push(Vec3::new(x, y, z));
+                }
+                "vn" => {
+                    let [x, y, z] = numbers(line_number, &words, 3, [0.; 3])?;
+                    normals.push(Vec3::new(x, y, z));
+                }
+                "vt" => {
+                    let [u, v, _] = numbers(line_number, &words, 1, [0.; 3])?;
+                    uvs.push(Vec2::new(u, v));
+                }
+                "f" => {
+                    if words.len() < 4 {
+                        return Err(syntax_error(line_number, column, "a face needs at least 3 vertices"));
+                    }
+                    let mut face = Vec::with_capacity(words.len() - 1);
+                    for &(column, word) in &words[1..] {
+                        let key = Self::face_vertex(line_number, column, word, positions.len(), uvs.len(), normals.len())?;
+                        face.push(current.vertex(key, &positions, &uvs, &normals));
+                    }
+                    // polygons are assumed to be convex and split into a fan
+                    for i in 1..face.len() - 1 {
+                        current.mesh.triangles.push([face[0], face[i], face[i + 1]]);
+                    }
+                }
+                "o" | "g" | "usemtl" => {
+                    let argument = line[line.find(keyword).unwrap() + keyword.len()..].trim();
+                    if keyword == "usemtl" {
+                        material = Some(obj.materials.iter().position(|m| m.name == argument)
+                            .ok_or_else(|| syntax_error(line_number, column, format!("unknown material '{}'", argument)))?);
+                    } else {
+                        name = argument.to_string();
+                    }
+                    let finished = std::mem::replace(&mut current, MeshBuilder::new(&name, material));
+                    obj.meshes.extend(finished.finish());
+                }
+                "mtllib" => {
+                    if words.len() < 2 {
+                        return Err(syntax_error(line_number, column, "'mtllib' expects a file name"));
+                    }
+                    for &(_, file) in &words[1..] {
+                        let path = base_path.join(file);
+                        let materials = parse_mtl(&read(&path)?).map_err(in_file(&path))?;
+                        obj.materials.extend(materials);
+                    }
+                }
+                // smoothing groups, lines, points and free form geometry are ignored
+                _ => {}
+            }
+        }
+        obj.meshes.extend(current.finish());
+        Ok(obj)
+    }
+
+    // resolves "v", "v/vt", "v//vn" and "v/vt/vn", where negative indices count back from the last element
+    fn face_vertex(line: usize, column: usize, word: &str, positions: usize, uvs: usize, normals: usize) -> Result<(usize, Option<usize>, Option<usize>), SceneError> {
+        let parts: Vec<&str> = word.split('/').collect();
+        if parts.len() > 3 || parts[0].is_empty() {
+            return Err(syntax_error(line, column, format!("invalid face vertex '{}'", word)));
+        }
+        let resolve = |part: &str, count: usize, kind: &str| -> Result<Option<usize>, SceneError> {
+            if part.is_empty() {
+                return Ok(None);
+            }
+            let index: i64 = part.parse().map_err(|_| syntax_error(line, column, format!("invalid face vertex '{}'", word)))?;
+            let resolved = if index < 0 { count as i64 + index } else { index - 1 };
+            if index == 0 || resolved < 0 || resolved >= count as i64 {
+                return Err(syntax_error(line, column, format!("{} index {} out of range, there are {}", kind, index, count)));
+            }
+            Ok(Some(resolved as usize))
+        };
+        Ok((
+            resolve(parts[0], positions, "position")?.unwrap(),
+            resolve(parts.get(1).copied().unwrap_or(""), uvs, "texture coordinate")?,
+            resolve(parts.get(2).copied().unwrap_or(""), normals, "normal")?,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod obj_tests {
+    use std::path::Path;
+    use crate::color::Color;
+    use crate::math::{Vec2, Vec3};
+    use crate::scene::obj::{parse_mtl, Obj};
+
+    #[test]
+    fn shared_vertices() {
+        let obj = Obj::parse("
+            v 0 0 0
+            v 1 0 0
+            v 1 1 0
+            v 0 1 0
+            vt 0 0
+            vn 0 0 1
+            o quad
+            f 1/1/1 2/1/1 3/1/1 4/1/1
+            g negative
+            f -4//1 -3//1 -1//1
+        ", Path::new("")).unwrap();
+        assert_eq!(obj.meshes.len(), 2);
+        let quad = &obj.meshes[0];
+        assert_eq!(quad.name, "quad");
+        assert_eq!(quad.positions.len(), 4);
+        assert_eq!(quad.triangles, vec![[0, 1, 2], [0, 2, 3]]);
+        assert_eq!(quad.uvs, vec![Vec2::new(0., 0.); 4]);
+        let negative = &obj.meshes[1];
+        assert_eq!(negative.name, "negative");
+        assert_eq!(negative.positions, vec![Vec3::new(0., 0., 0.), Vec3::new(1., 0., 0.), Vec3::new(0., 1., 0.)]);
+        assert_eq!(negative.normals, vec![Vec3::new(0., 0., 1.); 3]);
+        assert!(negative.uvs.is_empty());
+    }
+
+    #[test]
+    fn materials() {
+        let materials = parse_mtl("
+            newmtl red
+            Kd 1 0 0
+            newmtl glass # comment
+            illum 7
+            Ni 1.33
+            Kd 0.5
+            newmtl lamp
+            Kd 0.8
+            Ke 4 4 3
+        ").unwrap();
+        assert_eq!(materials.len(), 3);
+        assert_eq!(materials[0].name, "red");
+        assert_eq!(materials[0].diffuse, Color::new(1., 0., 0.));
+        assert_eq!(materials[1].name, "glass");
+        assert_eq!(materials[1].illum, 7);
+        assert_eq!(materials[1].ior, 1.33);
+        assert_eq!(materials[1].diffuse, Color::new(0.5, 0.5, 0.5));
+        assert_eq!(materials[0].to_material().emission(), Color::BLACK);
+        assert_eq!(materials[2].to_material().emission(), Color::new(4., 4., 3.));
+        // glass without Ns is smooth
+        assert!(materials[1].to_material().is_specular());
+    }
+
+    #[test]
+    fn roughness() {
+        let materials = parse_mtl("
+            newmtl glass
+            illum 7
+            Ns 500
+            newmtl frosted
+            illum 7
+            Ns 20
+            newmtl chrome
+            illum 3
+            Ks 0.9 0.9 0.9
+            newmtl brushed
+            illum 3
+            Ks 0.9 0.9 0.9
+            Pr 0.5
+        ").unwrap();
+        // typical exporter values for glass are smooth
+        assert!(materials[0].to_material().is_specular());
+        assert!(!materials[1].to_material().is_specular());
+        assert!(materials[2].to_material().is_specular());
+        assert!(!materials[3].to_material().is_specular());
+        assert_eq!(materials[3].roughness(), 0.5);
+    }
+
+    #[test]
+    fn errors() {
+        let error = |source: &str| Obj::parse(source, Path::new("")).unwrap_err().to_string();
+        assert_eq!(error("v 0 0 0\nv 1 0 x"), "line 2, column 7: expected a number, found 'x'");
+        assert_eq!(error("v 0 0 0\nf 1 2 3"), "line 2, column 5: position index 2 out of range, there are 1");
+        assert_eq!(error("v 0 0 0\n  f 1 1"), "line 2, column 3: a face needs at least 3 vertices");
+        assert_eq!(error("usemtl missing"), "line 1, column 1: unknown material 'missing'");
+        assert!(error("mtllib does_not_exist.mtl").starts_with("could not read does_not_exist.mtl"));
+        assert_eq!(parse_mtl("Kd 1 1 1").unwrap_err().to_string(), "line 1, column 1: 'Kd' before the first 'newmtl'");
+    }
+}
@@ -0,0 +1,1290 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use crate::camera::{Aperture, Camera, OrthographicCamera, ThinLensCamera};
+use crate::camera::builder::{CameraBuilder, FieldOfView};
+use crate::camera::cubemap::{CubemapCamera, CubemapLayout};
+use crate::camera::equirectangular::EquirectangularCamera;
+use crate::camera::fisheye::{FisheyeCamera, FisheyeProjection};
+use crate::camera::stereo::{OmniStereoCamera, StereoCamera, StereoLayout};
+use crate::color::Color;
+use crate::filters::box_filter::BoxFilter;
+use crate::filters::Filter;
+use crate::filters::gaussian::GaussianFilter;
+use crate::filters::lanczos::LanczosFilter;
+use crate::filters::mitchell::MitchellFilter;
+use crate::filters::tent::TentFilter;
+use crate::geometry::{Aabb, Geometry, Instance, Sphere, Triangle};
+use crate::groups::bvh_group::BvhGroup;
+use crate::lights::directional::DirectionalLight;
+use crate::lights::disk::DiskLight;
+use crate::lights::point::PointLight;
+use crate::lights::rectangle::RectangleLight;
+use crate::lights::sky::SkyLight;
+use crate::lights::sphere::SphereLight;
+use crate::lights::spot::SpotLight;
+use crate::lights::triangle::TriangleLight;
+use crate::materials::conductor::RoughConductor;
+use crate::materials::dielectric::Dielectric;
+use crate::materials::emissive::Emissive;
+use crate::materials::lambertian::Lambertian;
+use crate::materials::Material;
+use crate::materials::MaterialId;
+use crate::materials::mirror::Mirror;
+use crate::materials::rough_dielectric::RoughDielectric;
+use crate::math::{Transform, Vec2, Vec3, Vector};
+use crate::render::Renderer;
+use crate::scene::{IntegratorSettings, Scene, SceneError};
+use crate::scene::hdr::HdrImage;
+use crate::scene::obj::Obj;
+use crate::scene::tokenizer::Token;
+use crate::world::World;
+
+// a mesh in object space that can be instanced, with the material from its mtl file
+type SharedMesh = (Arc<dyn Geometry>, Option<MaterialId>);
+
+enum CameraSettings {
+    Perspective {
+        view: CameraBuilder,
+        // lens radius, a pinhole if not given. The token reports a failed autofocus
+        aperture: Option<(f32, Token)>,
+        focus: Focus,
+        shape: Aperture,
+        cat_eye: f32,
+    },
+    Orthographic {
+        origin: Vec3,
+        forward: Vec3,
+        up: Vec3,
+        size: Vec2,
+    },
+    Equirectangular {
+        origin: Vec3,
+        forward: Vec3,
+        up: Vec3,
+    },
+    Fisheye {
+        origin: Vec3,
+        forward: Vec3,
+        up: Vec3,
+        fov: f32,
+        aspect: Option<f32>,
+        projection: FisheyeProjection,
+    },
+    Cubemap {
+        origin: Vec3,
+        layout: CubemapLayout,
+    },
+    Stereo {
+        // the view from between the eyes
+        view: CameraBuilder,
+        interocular: f32,
+        convergence: f32,
+        layout: StereoLayout,
+    },
+    OmniStereo {
+        origin: Vec3,
+        forward: Vec3,
+        up: Vec3,
+        interocular: f32,
+        convergence: f32,
+        layout: StereoLayout,
+    },
+}
+
+enum Focus {
+    Distance(f32),
+    // focuses on whatever is visible at these image coordinates
+    Auto(Vec2),
+}
+
+pub(crate) struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+    base_path: PathBuf,
+    materials: HashMap<String, MaterialId>,
+    // meshes of every loaded obj file in object space, with the materials of the mtl file
+    meshes: HashMap<PathBuf, Vec<SharedMesh>>,
+    world: World,
+    camera: Option<CameraSettings>,
+    renderer: Renderer,
+    integrator: IntegratorSettings,
+    output: Option<String>,
+}
+
+impl Parser {
+    pub(crate) fn new(tokens: Vec<Token>, base_path: &Path) -> Self {
+        Parser {
+            tokens,
+            position: 0,
+            base_path: base_path.to_path_buf(),
+            materials: HashMap::new(),
+            meshes: HashMap::new(),
+            world: World::new(Box::new(BvhGroup::new())),
+            camera: None,
+            renderer: Renderer::new((512, 512)),
+            integrator: IntegratorSettings::PathTrace {
+                max_depth: 8,
+                rr_depth: 3,
+            },
+            output: None,
+        }
+    }
+
+    pub(crate) fn parse(mut self) -> Result<Scene, SceneError> {
+        while self.peek().is_some() {
+            self.statement(Transform::IDENTITY, true)?;
+        }
+        // autofocus needs the finished geometry
+        self.world.geometry.build();
+        let resolution = self.renderer.resolution;
+        let camera: Box<dyn Camera> = match self.camera.take() {
+            Some(CameraSettings::Perspective { view, aperture, focus, shape, cat_eye }) => {
+                let pinhole = view.build(resolution);
+                match aperture {
+                    None => Box::new(pinhole),
+                    Some((aperture, token)) => {
+                        let distance = match focus {
+                            Focus::Distance(distance) => distance,
+                            Focus::Auto(coords) => pinhole.focus_distance(&self.world, coords).ok_or_else(|| {
+                                token.error(format!("autofocus found nothing at ({}, {}); set focus_distance", coords.x, coords.y))
+                            })?,
+                        };
+                        Box::new(ThinLensCamera::new(pinhole, aperture, distance)
+                            .with_aperture(shape)
+                            .with_cat_eye(cat_eye))
+                    }
+                }
+            }
+            Some(CameraSettings::Orthographic { origin, forward, up, size }) => {
+                Box::new(OrthographicCamera::new(origin, forward, up, size))
+            }
+            Some(CameraSettings::Equirectangular { origin, forward, up }) => {
+                Box::new(EquirectangularCamera::new(origin, forward, up))
+            }
+            Some(CameraSettings::Fisheye { origin, forward, up, fov, aspect, projection }) => {
+                let aspect = aspect.unwrap_or(resolution.0 as f32 / resolution.1 as f32);
+                Box::new(FisheyeCamera::new(origin, forward, up, aspect, fov, projection))
+            }
+            Some(CameraSettings::Cubemap { origin, layout }) => Box::new(CubemapCamera::new(origin, layout)),
+            Some(CameraSettings::Stereo { view, interocular, convergence, layout }) => {
+                let center = view.build(layout.eye_resolution(resolution));
+                Box::new(StereoCamera::new(center, interocular, convergence, layout))
+            }
+            Some(CameraSettings::OmniStereo { origin, forward, up, interocular, convergence, layout }) => {
+                let panorama = EquirectangularCamera::new(origin, forward, up);
+                Box::new(OmniStereoCamera::new(panorama, interocular, convergence, layout))
+            }
+            None => return Err(SceneError::Syntax {
+                line: 1,
+                column: 1,
+                message: "the scene has no camera".into(),
+            }),
+        };
+        Ok(Scene {
+            world: self.world,
+            camera,
+            renderer: self.renderer,
+            integrator: self.integrator,
+            output: self.output,
+        })
+    }
+
+    // token helpers
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Result<Token, SceneError> {
+        match self.tokens.get(self.position) {
+            Some(token) => {
+                self.position += 1;
+                Ok(token.clone())
+            }
+            None => Err(self.end_of_file()),
+        }
+    }
+
+    fn end_of_file(&self) -> SceneError {
+        let (line, column) = self.tokens.last()
+            .map(|t| (t.line, t.column + t.text.len()))
+            .unwrap_or((1, 1));
+        SceneError::Syntax {
+            line,
+            column,
+            message: "unexpected end of file".into(),
+        }
+    }
+
+    fn expect(&mut self, text: &str) -> Result<Token, SceneError> {
+        let token = self.next()?;
+        if token.quoted || token.text != text {
+            return Err(token.error(format!("expected '{}', found '{}'", text, token.text)));
+        }
+        Ok(token)
+    }
+
+    fn word(&mut self) -> Result<Token, SceneError> {
+        let token = self.next()?;
+        if token.quoted || token.text == "{" || token.text == "}" {
+            return Err(token.error(format!("expected a name, found '{}'", token.text)));
+        }
+        Ok(token)
+    }
+
+    fn string(&mut self) -> Result<String, SceneError> {
+        Ok(self.next()?.text)
+    }
+
+    fn number(&mut self) -> Result<f32, SceneError> {
+        let token = self.next()?;
+        match token.text.parse::<f32>() {
+            Ok(value) if !token.quoted && value.is_finite() => Ok(value),
+            _ => Err(token.error(format!("expected a number, found '{}'", token.text))),
+        }
+    }
+
+    fn positive_number(&mut self) -> Result<f32, SceneError> {
+        let token = self.peek().cloned();
+        let value = self.number()?;
+        if value <= 0. {
+            return Err(token.unwrap().error(format!("expected a positive number, found '{}'", value)));
+        }
+        Ok(value)
+    }
+
+    fn non_negative_number(&mut self) -> Result<f32, SceneError> {
+        let token = self.peek().cloned();
+        let value = self.number()?;
+        if value < 0. {
+            return Err(token.unwrap().error(format!("expected a non-negative number, found '{}'", value)));
+        }
+        Ok(value)
+    }
+
+    fn integer(&mut self) -> Result<u32, SceneError> {
+        let token = self.next()?;
+        match token.text.parse::<u32>() {
+            Ok(value) if !token.quoted => Ok(value),
+            _ => Err(token.error(format!("expected a positive integer, found '{}'", token.text))),
+        }
+    }
+
+    fn vec2(&mut self) -> Result<Vec2, SceneError> {
+        Ok(Vec2::new(self.number()?, self.number()?))
+    }
+
+    fn vec3(&mut self) -> Result<Vec3, SceneError> {
+        Ok(Vec3::new(self.number()?, self.number()?, self.number()?))
+    }
+
+    // directions and edges, which can't be normalized or span nothing when they are 0
+    fn nonzero_vec3(&mut self, key: &Token) -> Result<Vec3, SceneError> {
+        let value = self.vec3()?;
+        if value.length_squared() == 0. {
+            return Err(key.error(format!("'{}' must not be 0", key.text)));
+        }
+        Ok(value)
+    }
+
+    // an opening angle in degrees, a pinhole can't see 180° or more
+    fn field_of_view(&mut self, key: &Token) -> Result<f32, SceneError> {
+        let value = self.positive_number()?;
+        if value >= 180. {
+            return Err(key.error(format!("'{}' has to be less than 180°", key.text)));
+        }
+        Ok(value)
+    }
+
+    // a single number is a shade of gray
+    fn color(&mut self) -> Result<Color, SceneError> {
+        let r = self.number()?;
+        if self.peek().is_some_and(|t| t.text.parse::<f32>().is_ok() && !t.quoted) {
+            Ok(Color::new(r, self.number()?, self.number()?))
+        } else {
+            Ok(Color::new(r, r, r))
+        }
+    }
+
+    fn material_ref(&mut self) -> Result<MaterialId, SceneError> {
+        let token = self.word()?;
+        self.materials.get(&token.text).copied()
+            .ok_or_else(|| token.error(format!("unknown material '{}'", token.text)))
+    }
+
+    // parses "{ key values... }", handing every key to the closure
+    fn block(&mut self, mut entry: impl FnMut(&mut Self, &Token) -> Result<(), SceneError>) -> Result<(), SceneError> {
+        self.expect("{")?;
+        loop {
+            let key = self.next()?;
+            if key.text == "}" && !key.quoted {
+                return Ok(());
+            }
+            entry(self, &key)?;
+        }
+    }
+
+    fn optional_block(&mut self, entry: impl FnMut(&mut Self, &Token) -> Result<(), SceneError>) -> Result<(), SceneError> {
+        if self.peek().is_some_and(|t| t.text == "{" && !t.quoted) {
+            self.block(entry)
+        } else {
+            Ok(())
+        }
+    }
+
+    // x|y|z|<axis> <degrees>
+    fn rotation(&mut self) -> Result<Transform, SceneError> {
+        let axis_token = self.next()?;
+        let axis = match axis_token.text.as_str() {
+            "x" => Vec3::X,
+            "y" => Vec3::Y,
+            "z" => Vec3::Z,
+            _ => {
+                self.position -= 1;
+                let axis = self.vec3()?;
+                if axis.length_squared() == 0. {
+                    return Err(axis_token.error("the rotation axis must not be 0"));
+                }
+                axis
+            }
+        };
+        Ok(Transform::rotate(axis, self.number()?.to_radians()))
+    }
+
+    fn required<T>(value: Option<T>, at: &Token, name: &str) -> Result<T, SceneError> {
+        value.ok_or_else(|| at.error(format!("'{}' is missing", name)))
+    }
+
+    // statements
+
+    fn statement(&mut self, transform: Transform, top_level: bool) -> Result<(), SceneError> {
+        let keyword = self.word()?;
+        match keyword.text.as_str() {
+            "transform" => self.transform(transform),
+            "sphere" => self.sphere(&keyword, transform),
+            "box" => self.aabb(&keyword, transform),
+            "triangle" => self.triangle(&keyword, transform),
+            "mesh" => self.mesh(&keyword, transform),
+            "resolution" | "samples" | "threads" | "filter" | "output" | "camera" | "integrator" | "material" | "light"
+                if !top_level => Err(keyword.error(format!("'{}' is not allowed inside a transform", keyword.text))),
+            "resolution" => {
+                self.renderer.resolution = (self.integer()?, self.integer()?);
+                if self.renderer.resolution.0 == 0 || self.renderer.resolution.1 == 0 {
+                    return Err(keyword.error("the resolution must not be 0"));
+                }
+                Ok(())
+            }
+            "samples" => {
+                self.renderer.samples_per_pixel = self.integer()?.max(1);
+                Ok(())
+            }
+            "threads" => {
+                self.renderer.threads = self.integer()?.max(1) as usize;
+                Ok(())
+            }
+            "output" => {
+                self.output = Some(self.string()?);
+                Ok(())
+            }
+            "filter" => self.filter(),
+            "camera" => self.camera(),
+            "integrator" => self.integrator(),
+            "material" => self.material(),
+            "light" => self.light(),
+            _ => Err(keyword.error(format!("unknown statement '{}'", keyword.text))),
+        }
+    }
+
+    // every statement is applied before the ones above it, so "translate" followed by "rotate" rotates the shapes
+    // around their own origin before moving them
+    fn transform(&mut self, outer: Transform) -> Result<(), SceneError> {
+        self.expect("{")?;
+        let mut transform = outer;
+        loop {
+            let Some(token) = self.peek().cloned() else {
+                return Err(self.end_of_file());
+            };
+            match token.text.as_str() {
+                "}" if !token.quoted => {
+                    self.next()?;
+                    return Ok(());
+                }
+                "translate" => {
+                    self.next()?;
+                    transform = transform * Transform::translate(self.vec3()?);
+                }
+                "scale" => {
+                    self.next()?;
+                    let x = self.number()?;
+                    let scale = if self.peek().is_some_and(|t| t.text.parse::<f32>().is_ok()) {
+                        Vec3::new(x, self.number()?, self.number()?)
+                    } else {
+                        Vec3::from(x)
+                    };
+                    if scale.x == 0. || scale.y == 0. || scale.z == 0. {
+                        return Err(token.error("scale factors must not be 0"));
+                    }
+                    transform = transform * Transform::scale(scale);
+                }
+                "rotate" => {
+                    self.next()?;
+                    transform = transform * self.rotation()?;
+                }
+                _ => self.statement(transform, false)?,
+            }
+        }
+    }
+
+    fn sphere(&mut self, keyword: &Token, transform: Transform) -> Result<(), SceneError> {
+        let (mut center, mut radius, mut material) = (None, None, None);
+        self.block(|p, key| {
+            match key.text.as_str() {
+                "center" => center = Some(p.vec3()?),
+                "radius" => radius = Some(p.positive_number()?),
+                "material" => material = Some(p.material_ref()?),
+                _ => return Err(key.error(format!("unknown sphere property '{}'", key.text))),
+            }
+            Ok(())
+        })?;
+        let center = Self::required(center, keyword, "center")?;
+        let radius = Self::required(radius, keyword, "radius")?;
+        // only spheres that would be distorted need to be instanced
+        let sphere: Box<dyn Geometry> = if transform.is_similarity() {
+            Box::new(Sphere::new(transform.point(center), radius * transform.vector(Vec3::X).length()))
+        } else {
+            Box::new(Instance::new(Arc::new(Sphere::new(center, radius)), transform))
+        };
+        self.world.add_geometry(sphere, material);
+        Ok(())
+    }
+
+    fn aabb(&mut self, keyword: &Token, transform: Transform) -> Result<(), SceneError> {
+        let (mut min, mut max, mut material) = (None, None, None);
+        self.block(|p, key| {
+            match key.text.as_str() {
+                "min" => min = Some(p.vec3()?),
+                "max" => max = Some(p.vec3()?),
+                "material" => material = Some(p.material_ref()?),
+                _ => return Err(key.error(format!("unknown box property '{}'", key.text))),
+            }
+            Ok(())
+        })?;
+        let aabb = Aabb::new(Self::required(min, keyword, "min")?, Self::required(max, keyword, "max")?);
+        let aabb: Box<dyn Geometry> = if transform.is_axis_aligned() {
+            Box::new(transform.aabb(&aabb))
+        } else {
+            Box::new(Instance::new(Arc::new(aabb), transform))
+        };
+        self.world.add_geometry(aabb, material);
+        Ok(())
+    }
+
+    fn triangle(&mut self, keyword: &Token, transform: Transform) -> Result<(), SceneError> {
+        let mut vertices = [None; 3];
+        let mut normals = [None; 3];
+        let mut material = None;
+        self.block(|p, key| {
+            match key.text.as_str() {
+                "v0" => vertices[0] = Some(p.vec3()?),
+                "v1" => vertices[1] = Some(p.vec3()?),
+                "v2" => vertices[2] = Some(p.vec3()?),
+                "n0" => normals[0] = Some(p.nonzero_vec3(key)?),
+                "n1" => normals[1] = Some(p.nonzero_vec3(key)?),
+                "n2" => normals[2] = Some(p.nonzero_vec3(key)?),
+                "material" => material = Some(p.material_ref()?),
+                _ => return Err(key.error(format!("unknown triangle property '{}'", key.text))),
+            }
+            Ok(())
+        })?;
+        let mut v = [
+            transform.point(Self::required(vertices[0], keyword, "v0")?),
+            transform.point(Self::required(vertices[1], keyword, "v1")?),
+            transform.point(Self::required(vertices[2], keyword, "v2")?),
+        ];
+        let mut n = normals.map(|n| n.map(|n| transform.normal(n)));
+        // mirroring turns the winding around, which would flip the face normal
+        if transform.swaps_handedness() {
+            v.swap(1, 2);
+            n.swap(1, 2);
+        }
+        // vertex normals are optional, but either all or none have to be given
+        let triangle = match n {
+            [None, None, None] => Triangle::new(v[0], v[1], v[2]),
+            [Some(n0), Some(n1), Some(n2)] => Triangle::with_normals(v[0], v[1], v[2], [n0, n1, n2]),
+            _ => return Err(keyword.error("either all or none of the vertex normals have to be given")),
+        };
+        self.world.add_geometry(Box::new(triangle), material);
+        Ok(())
+    }
+
+    fn mesh(&mut self, keyword: &Token, transform: Transform) -> Result<(), SceneError> {
+        let (mut file, mut material) = (None, None);
+        self.block(|p, key| {
+            match key.text.as_str() {
+                "file" => file = Some(p.string()?),
+                // used for the faces without a material in the mtl file
+                "material" => material = Some(p.material_ref()?),
+                _ => return Err(key.error(format!("unknown mesh property '{}'", key.text))),
+            }
+            Ok(())
+        })?;
+        let path = self.base_path.join(Self::required(file, keyword, "file")?);
+        // every file is only loaded once, further uses are instances of the same meshes
+        if !self.meshes.contains_key(&path) {
+            let obj = Obj::load(&path)?;
+            let materials: Vec<MaterialId> = obj.materials.iter()
+                .map(|m| self.world.add_material(m.to_material()))
+                .collect();
+            let meshes = obj.meshes.iter().map(|mesh| {
+                let mut triangles = mesh.to_triangle_mesh();
+                triangles.build();
+                (Arc::new(triangles) as Arc<dyn Geometry>, mesh.material.map(|m| materials[m]))
+            }).collect();
+            self.meshes.insert(path.clone(), meshes);
+        }
+        for (mesh, mesh_material) in &self.meshes[&path] {
+            let instance = Instance::new(mesh.clone(), transform);
+            self.world.add_geometry(Box::new(instance), mesh_material.or(material));
+        }
+        Ok(())
+    }
+
+    fn filter(&mut self) -> Result<(), SceneError> {
+        let kind = self.word()?;
+        let mut radius = None;
+        // filter specific parameters
+        let (mut sigma, mut b, mut c, mut tau) = (0.5, 1. / 3., 1. / 3., 2.);
+        let name = kind.text.clone();
+        self.optional_block(|p, key| {
+            match (name.as_str(), key.text.as_str()) {
+                (_, "radius") => radius = Some(Vec2::from(p.positive_number()?)),
+                ("gaussian", "sigma") => sigma = p.positive_number()?,
+                ("mitchell", "b") => b = p.number()?,
+                ("mitchell", "c") => c = p.number()?,
+                ("lanczos", "tau") => tau = p.positive_number()?,
+                _ => return Err(key.error(format!("unknown {} filter property '{}'", name, key.text))),
+            }
+            Ok(())
+        })?;
+        let filter: Box<dyn Filter> = match kind.text.as_str() {
+            "box" => Box::new(BoxFilter::new(radius.unwrap_or(Vec2::from(0.5)))),
+            "tent" => Box::new(TentFilter::new(radius.unwrap_or(Vec2::from(1.)))),
+            "gaussian" => Box::new(GaussianFilter::new(radius.unwrap_or(Vec2::from(1.5)), sigma)),
+            "mitchell" => Box::new(MitchellFilter::new(radius.unwrap_or(Vec2::from(2.)), b, c)),
+            "lanczos" => Box::new(LanczosFilter::new(radius.unwrap_or(Vec2::from(2.)), tau)),
+            _ => return Err(kind.error(format!("unknown filter '{}'", kind.text))),
+        };
+        self.renderer.filter = filter;
+        Ok(())
+    }
+
+    fn camera(&mut self) -> Result<(), SceneError> {
+        let kind = self.word()?;
+        let (mut origin, mut forward, mut target, mut up) = (None, None, None, None);
+        let (mut fov, mut aspect, mut size) = (None, None, None);
+        // a full frame sensor, if only the focal length is given
+        let (mut field_of_view, mut sensor) = (None, Vec2::new(36., 24.));
+        let (mut aperture, mut focus) = (None, Focus::Auto(Vec2::from(0.5)));
+        let (mut shape, mut blade_rotation, mut cat_eye) = (Aperture::Circle, 0., 0.);
+        let (mut projection, mut layout) = (FisheyeProjection::Equidistant, CubemapLayout::Cross);
+        // 6.4 cm for scenes in meters
+        let (mut interocular, mut convergence, mut stereo_layout) = (0.064, f32::INFINITY, None);
+        let name = kind.text.clone();
+        self.block(|p, key| {
+            match (name.as_str(), key.text.as_str()) {
+                (_, "origin") => origin = Some(p.vec3()?),
+                // cube maps are aligned with the world axes
+                (name, "forward" | "target") if name != "cubemap" => {
+                    if forward.is_some() || target.is_some() {
+                        return Err(key.error("a camera has either a 'forward' or a 'target', not both"));
+                    }
+                    if key.text == "forward" {
+                        forward = Some(p.nonzero_vec3(key)?);
+                    } else {
+                        target = Some((p.vec3()?, key.clone()));
+                    }
+                }
+                (name, "up") if name != "cubemap" => up = Some(p.nonzero_vec3(key)?),
+                ("fisheye", "fov") => fov = Some(p.positive_number()?),
+                ("perspective" | "stereo", "fov") => field_of_view = Some(FieldOfView::Horizontal(p.field_of_view(key)?)),
+                ("perspective" | "stereo", "vertical_fov") => field_of_view = Some(FieldOfView::Vertical(p.field_of_view(key)?)),
+                ("perspective" | "stereo", "focal_length") => field_of_view = Some(FieldOfView::Lens {
+                    sensor,
+                    focal_length: p.positive_number()?,
+                }),
+                ("perspective" | "stereo", "sensor") => sensor = Vec2::new(p.positive_number()?, p.positive_number()?),
+                ("perspective" | "fisheye" | "stereo", "aspect") => aspect = Some(p.positive_number()?),
+                ("perspective", "aperture") => aperture = Some((p.positive_number()?, key.clone())),
+                ("perspective", "focus_distance") => focus = Focus::Distance(p.positive_number()?),
+                ("perspective", "autofocus") => focus = Focus::Auto(p.vec2()?),
+                ("perspective", "blades") => {
+                    let blades = p.integer()?;
+                    if blades < 3 {
+                        return Err(key.error("an aperture needs at least 3 blades"));
+                    }
+                    shape = Aperture::polygon(blades, 0.);
+                }
+                ("perspective", "blade_rotation") => blade_rotation = p.number()?.to_radians(),
+                ("perspective", "aperture_mask") => {
+                    let file = p.string()?;
+                    let image = HdrImage::load(&p.base_path.join(file))?;
+                    let values: Vec<f32> = image.pixels.iter().map(|c| c.luminance()).collect();
+                    shape = Aperture::mask(image.width, image.height, &values)
+                        .ok_or_else(|| key.error("the aperture mask is black"))?;
+                }
+                ("perspective", "cat_eye") => cat_eye = p.positive_number()?,
+                ("orthographic", "size") => size = Some(p.vec2()?),
+                ("fisheye", "projection") => {
+                    let value = p.word()?;
+                    projection = match value.text.as_str() {
+                        "equidistant" => FisheyeProjection::Equidistant,
+                        "equisolid" => FisheyeProjection::Equisolid,
+                        _ => return Err(value.error(format!("unknown fisheye projection '{}'", value.text))),
+                    };
+                }
+                ("cubemap", "layout") => {
+                    let value = p.word()?;
+                    layout = match value.text.as_str() {
+                        "strip" => CubemapLayout::Strip,
+                        "cross" => CubemapLayout::Cross,
+                        _ => return Err(value.error(format!("unknown cube map layout '{}'", value.text))),
+                    };
+                }
+                ("stereo" | "omnistereo", "interocular") => interocular = p.positive_number()?,
+                ("stereo" | "omnistereo", "convergence") => convergence = p.positive_number()?,
+                ("stereo" | "omnistereo", "layout") => {
+                    let value = p.word()?;
+                    stereo_layout = Some(match value.text.as_str() {
+                        "side_by_side" => StereoLayout::SideBySide,
+                        "top_bottom" => StereoLayout::TopBottom,
+                        _ => return Err(value.error(format!("unknown stereo layout '{}'", value.text))),
+                    });
+                }
+                _ => return Err(key.error(format!("unknown {} camera property '{}'", name, key.text))),
+            }
+            Ok(())
+        })?;
+        let origin = Self::required(origin, &kind, "origin")?;
+        if kind.text == "cubemap" {
+            self.camera = Some(CameraSettings::Cubemap { origin, layout });
+            return Ok(());
+        }
+        // a target is the same as looking at it
+        if let Some((target, key)) = &target {
+            if (*target - origin).length_squared() == 0. {
+                return Err(key.error("'target' must not be the origin"));
+            }
+        }
+        let forward = Self::required(target.map(|(t, _)| t - origin).or(forward), &kind, "forward")?;
+        let up = Self::required(up, &kind, "up")?;
+        if forward.cross(&up).length_squared() == 0. {
+            return Err(kind.error("'up' must not be parallel to the view direction"));
+        }
+        let view = || -> Result<CameraBuilder, SceneError> {
+            let field_of_view = match Self::required(field_of_view, &kind, "fov")? {
+                // the sensor may be given after the focal length
+                FieldOfView::Lens { focal_length, .. } => FieldOfView::Lens { sensor, focal_length },
+                fov => fov,
+            };
+            let view = CameraBuilder::new().origin(origin).forward(forward).up(up).fov(field_of_view);
+            Ok(match aspect {
+                Some(aspect) => view.aspect(aspect),
+                None => view,
+            })
+        };
+        self.camera = Some(match kind.text.as_str() {
+            "perspective" => CameraSettings::Perspective {
+                view: view()?,
+                aperture,
+                focus,
+                shape: match shape {
+                    Aperture::Polygon { blades, .. } => Aperture::polygon(blades, blade_rotation),
+                    shape => shape,
+                },
+                cat_eye,
+            },
+            "orthographic" => CameraSettings::Orthographic {
+                origin,
+                forward,
+                up,
+                size: Self::required(size, &kind, "size")?,
+            },
+            "equirectangular" => CameraSettings::Equirectangular {
+                origin,
+                forward,
+                up,
+            },
+            "fisheye" => CameraSettings::Fisheye {
+                origin,
+                forward,
+                up,
+                fov: match Self::required(fov, &kind, "fov")? {
+                    fov if fov > 360. => return Err(kind.error("a fisheye can't see more than 360°")),
+                    fov => fov,
+                },
+                aspect,
+                projection,
+            },
+            "stereo" => CameraSettings::Stereo {
+                view: view()?,
+                interocular,
+                convergence,
+                layout: stereo_layout.unwrap_or(StereoLayout::SideBySide),
+            },
+            // stacking the panoramas keeps them at their usual 2:1
+            "omnistereo" => CameraSettings::OmniStereo {
+                origin,
+                forward,
+                up,
+                interocular,
+                convergence,
+                layout: stereo_layout.unwrap_or(StereoLayout::TopBottom),
+            },
+            _ => return Err(kind.error(format!("unknown camera '{}'", kind.text))),
+        });
+        Ok(())
+    }
+
+    fn integrator(&mut self) -> Result<(), SceneError> {
+        let kind = self.word()?;
+        match kind.text.as_str() {
+            "raytrace" => {
+                self.optional_block(|_, key| Err(key.error(format!("unknown raytrace integrator property '{}'", key.text))))?;
+                self.integrator = IntegratorSettings::RayTrace;
+            }
+            "path" => {
+                let (mut max_depth, mut rr_depth) = (8, 3);
+                self.optional_block(|p, key| {
+                    match key.text.as_str() {
+                        "max_depth" => max_depth = p.integer()?,
+                        "rr_depth" => rr_depth = p.integer()?,
+                        _ => return Err(key.error(format!("unknown path integrator property '{}'", key.text))),
+                    }
+                    Ok(())
+                })?;
+                self.integrator = IntegratorSettings::PathTrace { max_depth, rr_depth };
+            }
+            _ => return Err(kind.error(format!("unknown integrator '{}'", kind.text))),
+        }
+        Ok(())
+    }
+
+    fn material(&mut self) -> Result<(), SceneError> {
+        let name = self.word()?;
+        if self.materials.contains_key(&name.text) {
+            return Err(name.error(format!("material '{}' is already defined", name.text)));
+        }
+        let kind = self.word()?;
+        let (mut color, mut ior, mut roughness) = (Color::WHITE, 1.5, 0.);
+        let (mut eta, mut k, mut radiance) = (None, None, None);
+        let kind_name = kind.text.clone();
+        self.optional_block(|p, key| {
+            match (kind_name.as_str(), key.text.as_str()) {
+                ("lambertian" | "mirror", "color") => color = p.color()?,
+                ("emissive", "radiance") => radiance = Some(p.color()?),
+                ("dielectric" | "rough_dielectric", "ior") => ior = p.positive_number()?,
+                ("conductor" | "rough_dielectric", "roughness") => roughness = p.number()?.clamp(0., 1.),
+                ("conductor", "eta") => eta = Some(p.color()?),
+                ("conductor", "k") => k = Some(p.color()?),
+                ("conductor", "preset") => {
+                    let preset = p.word()?;
+                    let (e, kk) = match preset.text.as_str() {
+                        "gold" => RoughConductor::GOLD,
+                        "copper" => RoughConductor::COPPER,
+                        "aluminium" | "aluminum" => RoughConductor::ALUMINIUM,
+                        _ => return Err(preset.error(format!("unknown conductor preset '{}'", preset.text))),
+                    };
+                    eta = Some(e);
+                    k = Some(kk);
+                }
+                _ => return Err(key.error(format!("unknown {} property '{}'", kind_name, key.text))),
+            }
+            Ok(())
+        })?;
+        let material: Arc<dyn Material> = match kind.text.as_str() {
+            "lambertian" => Arc::new(Lambertian::new(color)),
+            "mirror" => Arc::new(Mirror::new(color)),
+            "dielectric" => Arc::new(Dielectric::new(ior)),
+            "rough_dielectric" => Arc::new(RoughDielectric::new(ior, roughness)),
+            "emissive" => Arc::new(Emissive::new(Self::required(radiance, &kind, "radiance")?)),
+            "conductor" => Arc::new(RoughConductor::new(
+                Self::required(eta, &kind, "eta")?,
+                Self::required(k, &kind, "k")?,
+                roughness,
+            )),
+            _ => return Err(kind.error(format!("unknown material type '{}'", kind.text))),
+        };
+        let id = self.world.add_material(material);
+        self.materials.insert(name.text, id);
+        Ok(())
+    }
+
+    fn light(&mut self) -> Result<(), SceneError> {
+        let kind = self.word()?;
+        match kind.text.as_str() {
+            "point" => {
+                let (mut position, mut intensity) = (None, None);
+                self.block(|p, key| {
+                    match key.text.as_str() {
+                        "position" => position = Some(p.vec3()?),
+                        "intensity" => intensity = Some(p.color()?),
+                        _ => return Err(key.error(format!("unknown point light property '{}'", key.text))),
+                    }
+                    Ok(())
+                })?;
+                self.world.lights.push(Box::new(PointLight::new(
+                    Self::required(position, &kind, "position")?,
+                    Self::required(intensity, &kind, "intensity")?,
+                )));
+            }
+            "spot" => {
+                let (mut position, mut direction, mut intensity, mut angle, mut falloff) = (None, None, None, 30., 5.);
+                self.block(|p, key| {
+                    match key.text.as_str() {
+                        "position" => position = Some(p.vec3()?),
+                        "direction" => direction = Some(p.nonzero_vec3(key)?),
+                        "intensity" => intensity = Some(p.color()?),
+                        // half angle of the cone and the width of its soft edge, in degrees
+                        "angle" => {
+                            angle = p.positive_number()?;
+                            if angle > 180. {
+                                return Err(key.error("'angle' has to be at most 180°"));
+                            }
+                        }
+                        "falloff" => falloff = p.non_negative_number()?,
+                        _ => return Err(key.error(format!("unknown spot light property '{}'", key.text))),
+                    }
+                    Ok(())
+                })?;
+                self.world.lights.push(Box::new(SpotLight::new(
+                    Self::required(position, &kind, "position")?,
+                    Self::required(direction, &kind, "direction")?,
+                    Self::required(intensity, &kind, "intensity")?,
+                    angle.to_radians(),
+                    falloff.to_radians(),
+                )));
+            }
+            "directional" => {
+                let (mut direction, mut irradiance, mut angular_diameter) = (None, None, 0.);
+                self.block(|p, key| {
+                    match key.text.as_str() {
+                        "direction" => direction = Some(p.nonzero_vec3(key)?),
+                        "irradiance" => irradiance = Some(p.color()?),
+                        // in degrees, the sun covers about 0.53
+                        "angular_diameter" => {
+                            angular_diameter = p.non_negative_number()?;
+                            if angular_diameter > 180. {
+                                return Err(key.error("'angular_diameter' has to be at most 180°"));
+                            }
+                        }
+                        _ => return Err(key.error(format!("unknown directional light property '{}'", key.text))),
+                    }
+                    Ok(())
+                })?;
+                self.world.lights.push(Box::new(DirectionalLight::new(
+                    Self::required(direction, &kind, "direction")?,
+                    Self::required(irradiance, &kind, "irradiance")?,
+                    angular_diameter.to_radians(),
+                )));
+            }
+            "rectangle" => {
+                let (mut corner, mut u, mut v, mut radiance) = (None, None, None, None);
+                self.block(|p, key| {
+                    match key.text.as_str() {
+                        "corner" => corner = Some(p.vec3()?),
+                        "u" => u = Some(p.nonzero_vec3(key)?),
+                        "v" => v = Some(p.nonzero_vec3(key)?),
+                        "radiance" => radiance = Some(p.color()?),
+                        _ => return Err(key.error(format!("unknown rectangle light property '{}'", key.text))),
+                    }
+                    Ok(())
+                })?;
+                let (u, v) = (Self::required(u, &kind, "u")?, Self::required(v, &kind, "v")?);
+                if u.cross(&v).length_squared() == 0. {
+                    return Err(kind.error("'u' and 'v' must not be parallel"));
+                }
+                self.world.lights.push(Box::new(RectangleLight::new(
+                    Self::required(corner, &kind, "corner")?,
+                    u,
+                    v,
+                    Self::required(radiance, &kind, "radiance")?,
+                )));
+            }
+            "disk" => {
+                let (mut center, mut normal, mut radius, mut radiance) = (None, None, None, None);
+                self.block(|p, key| {
+                    match key.text.as_str() {
+                        "center" => center = Some(p.vec3()?),
+                        "normal" => normal = Some(p.nonzero_vec3(key)?),
+                        "radius" => radius = Some(p.positive_number()?),
+                        "radiance" => radiance = Some(p.color()?),
+                        _ => return Err(key.error(format!("unknown disk light property '{}'", key.text))),
+                    }
+                    Ok(())
+                })?;
+                self.world.lights.push(Box::new(DiskLight::new(
+                    Self::required(center, &kind, "center")?,
+                    Self::required(normal, &kind, "normal")?,
+                    Self::required(radius, &kind, "radius")?,
+                    Self::required(radiance, &kind, "radiance")?,
+                )));
+            }
+            "sphere" => {
+                let (mut center, mut radius, mut radiance) = (None, None, None);
+                self.block(|p, key| {
+                    match key.text.as_str() {
+                        "center" => center = Some(p.vec3()?),
+                        "radius" => radius = Some(p.positive_number()?),
+                        "radiance" => radiance = Some(p.color()?),
+                        _ => return Err(key.error(format!("unknown sphere light property '{}'", key.text))),
+                    }
+                    Ok(())
+                })?;
+                self.world.lights.push(Box::new(SphereLight::new(
+                    Self::required(center, &kind, "center")?,
+                    Self::required(radius, &kind, "radius")?,
+                    Self::required(radiance, &kind, "radiance")?,
+                )));
+            }
+            "triangle" => {
+                let (mut vertices, mut radiance) = ([None; 3], None);
+                self.block(|p, key| {
+                    match key.text.as_str() {
+                        "v0" => vertices[0] = Some(p.vec3()?),
+                        "v1" => vertices[1] = Some(p.vec3()?),
+                        "v2" => vertices[2] = Some(p.vec3()?),
+                        "radiance" => radiance = Some(p.color()?),
+                        _ => return Err(key.error(format!("unknown triangle light property '{}'", key.text))),
+                    }
+                    Ok(())
+                })?;
+                let v0 = Self::required(vertices[0], &kind, "v0")?;
+                let v1 = Self::required(vertices[1], &kind, "v1")?;
+                let v2 = Self::required(vertices[2], &kind, "v2")?;
+                if (v1 - v0).cross(&(v2 - v0)).length_squared() == 0. {
+                    return Err(kind.error("the vertices must not lie on a line"));
+                }
+                self.world.lights.push(Box::new(TriangleLight::new(v0, v1, v2, Self::required(radiance, &kind, "radiance")?)));
+            }
+            "environment" => {
+                let (mut file, mut color, mut intensity, mut rotation) = (None, None, 1., Transform::IDENTITY);
+                self.block(|p, key| {
+                    match key.text.as_str() {
+                        "file" => file = Some(p.string()?),
+                        // a constant color instead of an image
+                        "color" => color = Some(p.color()?),
+                        "intensity" => intensity = p.number()?,
+                        "rotate" => rotation = rotation * p.rotation()?,
+                        _ => return Err(key.error(format!("unknown environment light property '{}'", key.text))),
+                    }
+                    Ok(())
+                })?;
+                let image = match (file, color) {
+                    (Some(file), None) => {
+                        let path = self.base_path.join(file);
+                        HdrImage::load(&path)?
+                    }
+                    (None, Some(color)) => HdrImage {
+                        width: 1,
+                        height: 1,
+                        pixels: vec![color],
+                    },
+                    _ => return Err(kind.error("an environment light needs either a file or a color")),
+                };
+                self.world.lights.push(Box::new(image.to_environment_light(rotation, intensity)));
+            }
+            "sky" => {
+                let (mut elevation, mut azimuth, mut turbidity, mut intensity, mut sun_size) = (45., 0., 3., 1., 0.53);
+                self.block(|p, key| {
+                    match key.text.as_str() {
+                        "elevation" => elevation = p.number()?,
+                        "azimuth" => azimuth = p.number()?,
+                        "turbidity" => {
+                            turbidity = p.number()?;
+                            if !(1.7..=10.).contains(&turbidity) {
+                                return Err(key.error("the turbidity has to be between 1.7 and 10"));
+                            }
+                        }
+                        "intensity" => intensity = p.number()?,
+                        "sun_size" => sun_size = p.number()?.max(0.),
+                        _ => return Err(key.error(format!("unknown sky light property '{}'", key.text))),
+                    }
+                    Ok(())
+                })?;
+                self.world.lights.push(Box::new(SkyLight::new(elevation, azimuth, turbidity, intensity, sun_size)));
+            }
+            _ => return Err(kind.error(format!("unknown light '{}'", kind.text))),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod parser_tests {
+    use std::path::Path;
+    use crate::color::Color;
+    use crate::materials::MaterialId;
+    use crate::math::{ApproxEq, Vec2, Vec3, Vector};
+    use crate::ray::Ray;
+    use crate::scene::{IntegratorSettings, Scene, TempDir};
+
+    fn error(source: &str) -> String {
+        match Scene::parse(source, Path::new("")) {
+            Ok(_) => panic!("expected an error"),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    #[test]
+    fn complete_scene() {
+        let scene = Scene::parse(r#"
+            resolution 40 30
+            samples 4
+            filter mitchell { b 0.5 c 0.25 }
+            output "out.png"
+            camera perspective { origin 0 0 -5 forward 0 0 1 up 0 1 0 fov 60 }
+            integrator path { max_depth 3 }
+            material red lambertian { color 1 0 0 }
+            material glass dielectric { ior 1.4 }
+            material gold conductor { preset gold roughness 0.3 }
+            light point { position 0 5 0 intensity 10 }
+            sphere { center 0 0 0 radius 1 material red }
+            transform {
+                translate 10 0 0
+                scale 2
+                sphere { center 0 0 0 radius 1 material glass }
+                box { min 0 0 0 max 1 1 1 }
+            }
+            triangle { v0 0 0 5 v1 1 0 5 v2 0 1 5 material gold }
+        "#, Path::new("")).unwrap();
+        assert_eq!(scene.renderer.resolution, (40, 30));
+        assert_eq!(scene.renderer.samples_per_pixel, 4);
+        assert_eq!(scene.output.as_deref(), Some("out.png"));
+        assert_eq!(scene.integrator, IntegratorSettings::PathTrace { max_depth: 3, rr_depth: 3 });
+        assert_eq!(scene.world.materials.len(), 3);
+        assert_eq!(scene.world.lights.len(), 1);
+        let bounds = scene.world.geometry.get_bounds();
+        assert_eq!(bounds.min, Vec3::new(-1., -2., -2.));
+        assert_eq!(bounds.max, Vec3::new(12., 2., 5.));
+        // the transformed sphere has radius 2
+        let hit = scene.world.geometry.intersect(&Ray::new(Vec3::new(10., 5., 0.), Vec3::new(0., -1., 0.), None, None)).unwrap();
+        assert_eq!(hit.distance, 3.);
+    }
+
+    #[test]
+    fn mesh() {
+        let dir = TempDir::new("parser_mesh");
+        std::fs::write(dir.join("quad.obj"), "mtllib quad.mtl\nv 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nusemtl red\nf 1 2 3 4\n").unwrap();
+        std::fs::write(dir.join("quad.mtl"), "newmtl red\nKd 1 0 0\n").unwrap();
+        std::fs::write(dir.join("test.scene"), "camera orthographic { origin 0 0 -1 forward 0 0 1 up 0 1 0 size 1 1 }\n\
+            transform { scale 2 mesh { file \"quad.obj\" } }").unwrap();
+        let scene = Scene::load(dir.join("test.scene")).unwrap();
+        assert_eq!(scene.world.materials.len(), 1);
+        let bounds = scene.world.geometry.get_bounds();
+        assert_eq!(bounds.max, Vec3::new(2., 2., 0.));
+        let hit = scene.world.geometry.intersect(&Ray::new(Vec3::new(1.5, 0.5, -1.), Vec3::Z, None, None)).unwrap();
+        assert_eq!(hit.material, Some(MaterialId(0)));
+        // the face is counter clockwise seen from +z
+        assert_eq!(hit.normal, Vec3::Z);
+        assert!(!hit.front_face);
+
+        std::fs::write(dir.join("broken.scene"), "mesh { file \"broken.obj\" }").unwrap();
+        std::fs::write(dir.join("broken.obj"), "v 0 0\n").unwrap();
+        let error = Scene::load(dir.join("broken.scene")).err().unwrap().to_string();
+        assert!(error.ends_with("broken.obj: line 1, column 1: 'v' expects 3 to 4 numbers, found 2"), "{}", error);
+    }
+
+    #[test]
+    fn instanced_meshes() {
+        let dir = TempDir::new("parser_instanced_meshes");
+        std::fs::write(dir.join("quad.obj"), "mtllib quad.mtl\nv 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nusemtl red\nf 1 2 3 4\n").unwrap();
+        std::fs::write(dir.join("quad.mtl"), "newmtl red\nKd 1 0 0\n").unwrap();
+        // a file used multiple times is only loaded once
+        std::fs::write(dir.join("forest.scene"), "camera orthographic { origin 0 0 -1 forward 0 0 1 up 0 1 0 size 1 1 }\n\
+            mesh { file \"quad.obj\" }\n\
+            transform { translate 5 0 0 rotate y 180 mesh { file \"quad.obj\" } }").unwrap();
+        let scene = Scene::load(dir.join("forest.scene")).unwrap();
+        assert_eq!(scene.world.materials.len(), 1);
+        let hit = scene.world.geometry.intersect(&Ray::new(Vec3::new(4.5, 0.5, -1.), Vec3::Z, None, None)).unwrap();
+        assert!(hit.normal.a_eq(&-Vec3::Z));
+        assert!(hit.front_face);
+    }
+
+    #[test]
+    fn rotations() {
+        let scene = Scene::parse(r#"
+            camera orthographic { origin 0 0 -1 forward 0 0 1 up 0 1 0 size 1 1 }
+            transform {
+                translate 0 0 10
+                rotate y 90
+                box { min 0 0 0 max 2 1 1 }
+                rotate 0 0 1 45
+                sphere { center 1 0 0 radius 1 }
+            }
+        "#, Path::new("")).unwrap();
+        let bounds = scene.world.geometry.get_bounds();
+        // the sphere ends up at (0, 0.71, 9.29)
+        assert!(bounds.min.a_eq(&Vec3::new(-1., -0.293, 8.)));
+        assert!(bounds.max.a_eq(&Vec3::new(1., 1.707, 10.293)));
+
+        // shapes that can't be transformed directly are instanced, their bounds are the transformed object bounds
+        let scene = Scene::parse(r#"
+            camera orthographic { origin 0 0 -1 forward 0 0 1 up 0 1 0 size 1 1 }
+            transform { rotate x 30 scale 1 2 1 box { min 0 0 0 max 1 1 1 } sphere { center 5 0 0 radius 1 } }
+        "#, Path::new("")).unwrap();
+        let b = scene.world.geometry.get_bounds();
+        assert!(b.min.a_eq(&Vec3::new(0., -2.232, -1.866)), "{:?}", b);
+        assert!(b.max.a_eq(&Vec3::new(6., 2.232, 1.866)), "{:?}", b);
+        assert_eq!(error("transform { rotate 0 0 0 30 }"), "line 1, column 20: the rotation axis must not be 0");
+    }
+
+    #[test]
+    fn area_lights() {
+        let scene = Scene::parse(r#"
+            camera perspective { origin 0 0 -5 forward 0 0 1 up 0 1 0 fov 45 }
+            light rectangle { corner 343 548.8 227 u 0 0 105 v -130 0 0 radiance 17 12 4 }
+            light disk { center 0 5 0 normal 0 -1 0 radius 0.5 radiance 10 }
+            light sphere { center 0 3 0 radius 1 radiance 2 }
+            light triangle { v0 0 4 0 v1 1 4 0 v2 0 4 1 radiance 5 }
+            light spot { position 0 5 0 direction 0 -1 0 intensity 100 angle 20 falloff 3 }
+            light directional { direction 1 -1 0 irradiance 3 angular_diameter 0.53 }
+            material lamp emissive { radiance 5 4 3 }
+            sphere { center 0 10 0 radius 1 material lamp }
+            transform { scale 1 2 1 box { min 0 0 0 max 1 1 1 material lamp } }
+            triangle { v0 0 0 0 v1 1 0 0 v2 0 1 0 }
+        "#, Path::new("")).unwrap();
+        // only geometry with an emissive material becomes a light
+        assert_eq!(scene.world.lights.len(), 8);
+        assert_eq!(error("light disk { center 0 5 0 normal 0 -1 0 radiance 10 }"), "line 1, column 7: 'radius' is missing");
+    }
+
+    #[test]
+    fn environment() {
+        let dir = TempDir::new("parser_environment");
+        let mut pfm = b"PF\n2 1\n-1\n".to_vec();
+        for v in [1f32, 1., 1., 0., 0., 0.] {
+            pfm.extend(v.to_le_bytes());
+        }
+        std::fs::write(dir.join("sky.pfm"), pfm).unwrap();
+        std::fs::write(dir.join("test.scene"), "camera perspective { origin 0 0 0 forward 0 0 1 up 0 1 0 fov 45 }\n\
+            light environment { file \"sky.pfm\" intensity 3 rotate y 90 }").unwrap();
+        let scene = Scene::load(dir.join("test.scene")).unwrap();
+        // the left half of the map starts at +x, which the rotation turns to -z
+        assert_eq!(scene.world.background(-Vec3::Z), Color::new(3., 3., 3.));
+        assert_eq!(scene.world.background(Vec3::Z), Color::BLACK);
+        let scene = Scene::parse("camera perspective { origin 0 0 0 forward 0 0 1 up 0 1 0 fov 45 }\n\
+            light environment { color 0.5 }", Path::new("")).unwrap();
+        assert_eq!(scene.world.background(Vec3::X), Color::new(0.5, 0.5, 0.5));
+        assert_eq!(error("light environment { intensity 2 }"), "line 1, column 7: an environment light needs either a file or a color");
+        let scene = Scene::parse("camera perspective { origin 0 0 0 forward 0 0 1 up 0 1 0 fov 45 }\n\
+            light sky { elevation 90 turbidity 2.5 intensity 0.1 }", Path::new("")).unwrap();
+        // the sun is straight above
+        assert!(scene.world.background(Vec3::Y).r > 1000.);
+        assert!(scene.world.background(Vec3::new(1., 0.5, 0.)).b > 0.);
+        assert_eq!(error("light sky { turbidity 20 }"), "line 1, column 13: the turbidity has to be between 1.7 and 10");
+    }
+
+    #[test]
+    fn depth_of_field() {
+        let world = "sphere { center 0 0 8 radius 2 }\n";
+        // focused on the front of the sphere
+        let scene = Scene::parse(&format!("{}camera perspective {{ origin 0 0 0 forward 0 0 1 up 0 1 0 fov 45 aperture 0.5 }}", world), Path::new("")).unwrap();
+        for lens in [Vec2::ZERO, Vec2::new(0.9, 0.3), Vec2::new(0.1, 1.)] {
+            let ray = scene.camera.at(Vec2::from(0.5), lens).unwrap();
+            assert!(ray.at(6. / ray.direction.z).a_eq(&Vec3::new(0., 0., 6.)));
+        }
+        let scene = Scene::parse(&format!("{}camera perspective {{ origin 0 0 0 forward 0 0 1 up 0 1 0 fov 45 aperture 0.5 focus_distance 3 }}", world), Path::new("")).unwrap();
+        let ray = scene.camera.at(Vec2::from(0.5), Vec2::ZERO).unwrap();
+        assert!(ray.at(3. / ray.direction.z).a_eq(&Vec3::new(0., 0., 3.)));
+        assert!(!ray.origin.a_eq(&Vec3::ZERO));
+        // the sample lands on the hexagon, a circle would place it at 0.5 from the middle
+        let scene = Scene::parse(&format!("{}camera perspective {{ origin 0 0 0 forward 0 0 1 up 0 1 0 fov 45 aperture 1 blades 6 blade_rotation 30 }}", world), Path::new("")).unwrap();
+        let ray = scene.camera.at(Vec2::from(0.5), Vec2::new(0.25, 0.5)).unwrap();
+        assert!(ray.origin.length().a_eq(&(0.75f32.sqrt() * 0.5f32.sqrt())));
+        assert_eq!(error("camera perspective { origin 0 0 0 forward 0 0 1 up 0 1 0 fov 45 aperture 1 blades 2 }"), "line 1, column 76: an aperture needs at least 3 blades");
+        // the default autofocus looks at the middle of the image, which is empty here
+        assert_eq!(error("camera perspective { origin 0 0 0 forward 0 1 0 up 0 0 1 fov 45 aperture 1 }"), "line 1, column 65: autofocus found nothing at (0.5, 0.5); set focus_distance");
+        assert_eq!(error(&format!("{}camera perspective {{ origin 0 0 0 forward 0 0 1 up 0 1 0 fov 45 aperture 1 autofocus 0 0 }}", world)), "line 2, column 65: autofocus found nothing at (0, 0); set focus_distance");
+    }
+
+    #[test]
+    fn camera_views() {
+        // the vertical angle is kept for any resolution
+        let scene = Scene::parse("resolution 300 100\ncamera perspective { origin 0 5 -5 target 0 0 0 up 0 1 0 vertical_fov 90 }", Path::new("")).unwrap();
+        assert!(scene.camera.at(Vec2::from(0.5), Vec2::ZERO).unwrap().direction.a_eq(&Vec3::new(0., -1., 1.).normalized()));
+        assert!(scene.camera.at(Vec2::new(0.5, 0.), Vec2::ZERO).unwrap().direction.a_eq(&Vec3::Z));
+        // 18mm on a 36mm wide sensor is 90° across the image
+        let scene = Scene::parse("resolution 200 100\ncamera perspective { origin 0 0 0 forward 0 0 1 up 0 1 0 focal_length 18 sensor 36 18 }", Path::new("")).unwrap();
+        assert!(scene.camera.at(Vec2::new(0., 0.5), Vec2::ZERO).unwrap().direction.a_eq(&Vec3::new(1., 0., 1.).normalized()));
+        assert_eq!(error("camera perspective { origin 0 0 0 target 0 0 1 up 0 1 0 }"), "line 1, column 8: 'fov' is missing");
+        assert_eq!(error("camera perspective { origin 0 0 0 target 0 0 1 forward 0 0 1 }"), "line 1, column 48: a camera has either a 'forward' or a 'target', not both");
+        assert_eq!(error("camera perspective { target 1 2 3 origin 1 2 3 up 0 1 0 fov 60 }"), "line 1, column 22: 'target' must not be the origin");
+        assert_eq!(error("camera perspective { origin 0 0 0 forward 0 0 0 }"), "line 1, column 35: 'forward' must not be 0");
+        assert_eq!(error("camera perspective { origin 0 0 0 forward 0 2 0 up 0 1 0 fov 60 }"), "line 1, column 8: 'up' must not be parallel to the view direction");
+    }
+
+    #[test]
+    fn panoramic_cameras() {
+        let scene = Scene::parse("resolution 400 300\ncamera cubemap { origin 1 2 3 layout cross }", Path::new("")).unwrap();
+        let ray = scene.camera.at(Vec2::new(0.375, 0.5), Vec2::ZERO).unwrap();
+        assert_eq!(ray.origin, Vec3::new(1., 2., 3.));
+        assert!(ray.direction.a_eq(&Vec3::Z));
+        assert!(scene.camera.at(Vec2::new(0.1, 0.1), Vec2::ZERO).is_none());
+        let scene = Scene::parse("camera equirectangular { origin 0 0 0 forward 1 0 0 up 0 1 0 }", Path::new("")).unwrap();
+        assert!(scene.camera.at(Vec2::new(0.5, 0.5), Vec2::ZERO).unwrap().direction.a_eq(&Vec3::X));
+        // the image circle fits the width of the image
+        let scene = Scene::parse("resolution 200 100\ncamera fisheye { origin 0 0 0 forward 0 0 1 up 0 1 0 fov 180 projection equisolid }", Path::new("")).unwrap();
+        assert!(scene.camera.at(Vec2::new(0.5, 0.), Vec2::ZERO).is_some());
+        assert!(scene.camera.at(Vec2::new(0.02, 0.), Vec2::ZERO).is_none());
+        assert_eq!(error("camera fisheye { origin 0 0 0 forward 0 0 1 up 0 1 0 fov 400 }"), "line 1, column 8: a fisheye can't see more than 360°");
+        assert_eq!(error("camera perspective { origin 0 0 0 forward 0 0 1 up 0 1 0 fov 180 }"), "line 1, column 58: 'fov' has to be less than 180°");
+        assert_eq!(error("camera stereo { vertical_fov 200 }"), "line 1, column 17: 'vertical_fov' has to be less than 180°");
+        assert_eq!(error("camera cubemap { origin 0 0 0 forward 0 0 1 }"), "line 1, column 31: unknown cubemap camera property 'forward'");
+    }
+
+    #[test]
+    fn stereo_cameras() {
+        // each eye gets a square half of the image
+        let scene = Scene::parse("resolution 200 100\ncamera stereo { origin 0 0 0 forward 0 0 1 up 0 1 0 fov 90 interocular 0.2 }", Path::new("")).unwrap();
+        let left = scene.camera.at(Vec2::new(0., 0.5), Vec2::ZERO).unwrap();
+        let right = scene.camera.at(Vec2::new(0.5, 0.5), Vec2::ZERO).unwrap();
+        assert!(left.origin.a_eq(&Vec3::new(0.1, 0., 0.)));
+        assert!(right.origin.a_eq(&Vec3::new(-0.1, 0., 0.)));
+        assert!(left.direction.a_eq(&right.direction));
+        assert!(left.direction.a_eq(&Vec3::new(1., 0., 1.).normalized()));
+        let scene = Scene::parse("camera omnistereo { origin 0 0 0 forward 0 0 1 up 0 1 0 convergence 2 }", Path::new("")).unwrap();
+        let left = scene.camera.at(Vec2::new(0.5, 0.25), Vec2::ZERO).unwrap();
+        let right = scene.camera.at(Vec2::new(0.5, 0.75), Vec2::ZERO).unwrap();
+        assert!(left.origin.a_eq(&Vec3::new(0.032, 0., 0.)));
+        assert!(right.origin.a_eq(&-left.origin));
+        assert_eq!(error("camera omnistereo { origin 0 0 0 forward 0 0 1 up 0 1 0 layout diagonal }"), "line 1, column 64: unknown stereo layout 'diagonal'");
+    }
+
+    #[test]
+    fn error_positions() {
+        assert_eq!(error("camera perspective {\n  origin 0 0 x\n}"), "line 2, column 14: expected a number, found 'x'");
+        assert_eq!(error("sphere { center 0 0 0 radius 1 material missing }"), "line 1, column 41: unknown material 'missing'");
+        assert_eq!(error("  sphere { radius 1 }"), "line 1, column 3: 'center' is missing");
+        assert_eq!(error("resolution 10 10\nlight point { position 0 0 0"), "line 2, column 29: unexpected end of file");
+        assert_eq!(error("transform { samples 4 }"), "line 1, column 13: 'samples' is not allowed inside a transform");
+        assert_eq!(error("frobnicate"), "line 1, column 1: unknown statement 'frobnicate'");
+        assert_eq!(error("resolution 10 10"), "line 1, column 1: the scene has no camera");
+        assert_eq!(error("triangle { n0 0 0 0 }"), "line 1, column 12: 'n0' must not be 0");
+        assert_eq!(error("light disk { normal 0 0 0 }"), "line 1, column 14: 'normal' must not be 0");
+        assert_eq!(error("light rectangle { corner 0 0 0 u 1 0 0 v 0 0 0 radiance 1 }"), "line 1, column 40: 'v' must not be 0");
+        assert_eq!(error("light rectangle { corner 0 0 0 u 1 0 0 v 2 0 0 radiance 1 }"), "line 1, column 7: 'u' and 'v' must not be parallel");
+        assert_eq!(error("light triangle { v0 0 3 0 v1 1 3 0 v2 2 3 0 radiance 5 }"), "line 1, column 7: the vertices must not lie on a line");
+    }
+
+    #[test]
+    fn spot_and_directional_bounds() {
+        assert_eq!(error("light spot { direction 0 0 0 }"), "line 1, column 14: 'direction' must not be 0");
+        assert_eq!(error("light directional { direction 0 0 0 }"), "line 1, column 21: 'direction' must not be 0");
+        assert_eq!(error("light spot { angle 0 }"), "line 1, column 20: expected a positive number, found '0'");
+        assert_eq!(error("light spot { angle 190 }"), "line 1, column 14: 'angle' has to be at most 180°");
+        assert_eq!(error("light spot { falloff -2 }"), "line 1, column 22: expected a non-negative number, found '-2'");
+        assert_eq!(error("light directional { angular_diameter -1 }"), "line 1, column 38: expected a non-negative number, found '-1'");
+        assert_eq!(error("light directional { angular_diameter 200 }"), "line 1, column 21: 'angular_diameter' has to be at most 180°");
+    }
+}
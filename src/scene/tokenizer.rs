@@ -0,0 +1,103 @@
+use crate::scene::SceneError;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Token {
+    pub text: String,
+    // quoted strings are never keywords or numbers
+    pub quoted: bool,
+    // 1-based position of the first character
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Token {
+    pub fn error(&self, message: impl Into<String>) -> SceneError {
+        SceneError::Syntax {
+            line: self.line,
+            column: self.column,
+            message: message.into(),
+        }
+    }
+}
+
+// splits the input into words, numbers, quoted strings and braces. '#' starts a comment until the end of the line
+pub fn tokenize(input: &str) -> Result<Vec<Token>, SceneError> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+    let (mut line, mut column) = (1, 1);
+    while let Some(&c) = chars.peek() {
+        let (start_line, start_column) = (line, column);
+        if c == '\n' {
+            chars.next();
+            line += 1;
+            column = 1;
+        } else if c.is_whitespace() {
+            chars.next();
+            column += 1;
+        } else if c == '#' {
+            while chars.peek().is_some_and(|&c| c != '\n') {
+                chars.next();
+            }
+        } else if c == '{' || c == '}' {
+            chars.next();
+            column += 1;
+            tokens.push(Token { text: c.to_string(), quoted: false, line: start_line, column: start_column });
+        } else if c == '"' {
+            chars.next();
+            column += 1;
+            let mut text = String::new();
+            loop {
+                match chars.next() {
+                    Some('"') => {
+                        column += 1;
+                        break;
+                    }
+                    Some('\n') | None => return Err(SceneError::Syntax {
+                        line: start_line,
+                        column: start_column,
+                        message: "unterminated string".into(),
+                    }),
+                    Some(c) => {
+                        column += 1;
+                        text.push(c);
+                    }
+                }
+            }
+            tokens.push(Token { text, quoted: true, line: start_line, column: start_column });
+        } else {
+            let mut text = String::new();
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() || c == '{' || c == '}' || c == '#' || c == '"' {
+                    break;
+                }
+                text.push(c);
+                chars.next();
+                column += 1;
+            }
+            tokens.push(Token { text, quoted: false, line: start_line, column: start_column });
+        }
+    }
+    Ok(tokens)
+}
+
+#[cfg(test)]
+mod tokenizer_tests {
+    use crate::scene::tokenizer::tokenize;
+
+    #[test]
+    fn positions() {
+        let tokens = tokenize("camera {\n  fov 45 # comment\n \"some file.obj\"}").unwrap();
+        let texts: Vec<&str> = tokens.iter().map(|t| t.text.as_str()).collect();
+        assert_eq!(texts, vec!["camera", "{", "fov", "45", "some file.obj", "}"]);
+        assert_eq!((tokens[2].line, tokens[2].column), (2, 3));
+        assert_eq!((tokens[3].line, tokens[3].column), (2, 7));
+        assert!(tokens[4].quoted);
+        assert_eq!((tokens[5].line, tokens[5].column), (3, 17));
+    }
+
+    #[test]
+    fn unterminated_string() {
+        let error = tokenize("output \"test.png\nsamples 4").unwrap_err();
+        assert_eq!(error.to_string(), "line 1, column 8: unterminated string");
+    }
+}
@@ -1,11 +1,53 @@
-use std::rc::Rc;
-use crate::groups::Group;
+use std::sync::Arc;
+use crate::color::Color;
+use crate::geometry::Geometry;
+use crate::groups::{Group, GroupContent};
 use crate::lights::LightSource;
-use crate::materials::Material;
+use crate::materials::{Material, MaterialId};
+use crate::materials::lambertian::Lambertian;
+use crate::math::Vec3;
 
 pub struct World {
     pub geometry: Box<dyn Group>,
-    pub materials: Vec<Rc<dyn Material>>,
+    pub materials: Vec<Arc<dyn Material>>,
     pub lights: Vec<Box<dyn LightSource>>
 }
 
+impl World {
+    pub fn new(geometry: Box<dyn Group>) -> Self {
+        World {
+            geometry,
+            materials: vec![],
+            lights: vec![],
+        }
+    }
+
+    pub fn add_material(&mut self, material: Arc<dyn Material>) -> MaterialId {
+        self.materials.push(material);
+        MaterialId(self.materials.len() - 1)
+    }
+
+    // geometry with an emissive material is registered as a light as well, so it is sampled directly
+    pub fn add_geometry(&mut self, geometry: Box<dyn Geometry>, material: Option<MaterialId>) {
+        let emission = self.material(material).emission();
+        if emission.max_component() > 0. {
+            if let Some(light) = geometry.light(emission) {
+                self.lights.push(light);
+            }
+        }
+        self.geometry.push(GroupContent::new(geometry, material));
+    }
+
+    // radiance of the lights at infinity, seen by rays that miss all geometry
+    pub fn background(&self, direction: Vec3) -> Color {
+        self.lights.iter().fold(Color::BLACK, |sum, light| sum + light.background(direction))
+    }
+
+    // geometry without a bound material is rendered white diffuse
+    pub fn material(&self, id: Option<MaterialId>) -> &dyn Material {
+        match id {
+            Some(MaterialId(index)) => &*self.materials[index],
+            None => &Lambertian::WHITE,
+        }
+    }
+}